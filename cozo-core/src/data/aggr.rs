@@ -421,12 +421,12 @@ define_aggr!(AGGR_COUNT, false);
 
 #[derive(Default)]
 pub(crate) struct AggrCount {
-    count: i64,
+    pub(crate) count: i64,
 }
 
 impl NormalAggrObj for AggrCount {
     fn set(&mut self, _value: &DataValue) -> Result<()> {
-        self.count += 1;
+        self.count = self.count.saturating_add(1);
         Ok(())
     }
 
@@ -521,6 +521,51 @@ impl NormalAggrObj for AggrMean {
     }
 }
 
+define_aggr!(AGGR_MEDIAN, false);
+define_aggr!(AGGR_PERCENTILE, false);
+
+/// Exact quantile of the values in a group, by linear interpolation between closest ranks.
+/// `median` is the quantile at 0.5.
+pub(crate) struct AggrQuantile {
+    name: &'static str,
+    q: f64,
+    accum: Vec<f64>,
+}
+
+impl AggrQuantile {
+    fn new(name: &'static str, q: f64) -> Self {
+        Self {
+            name,
+            q,
+            accum: vec![],
+        }
+    }
+}
+
+impl NormalAggrObj for AggrQuantile {
+    fn set(&mut self, value: &DataValue) -> Result<()> {
+        match value {
+            DataValue::Num(n) => self.accum.push(n.get_float()),
+            v => bail!("cannot compute '{}': encountered value {:?}", self.name, v),
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> Result<DataValue> {
+        if self.accum.is_empty() {
+            return Ok(DataValue::Null);
+        }
+        let mut sorted = self.accum.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = self.q * (sorted.len() - 1) as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        let frac = rank - lo as f64;
+        Ok(DataValue::from(
+            sorted[lo] + (sorted[hi] - sorted[lo]) * frac,
+        ))
+    }
+}
+
 define_aggr!(AGGR_SUM, false);
 
 #[derive(Default)]
@@ -1172,6 +1217,8 @@ pub(crate) fn parse_aggr(name: &str) -> Option<&'static Aggregation> {
         "min" => &AGGR_MIN,
         "max" => &AGGR_MAX,
         "mean" => &AGGR_MEAN,
+        "median" => &AGGR_MEDIAN,
+        "percentile" => &AGGR_PERCENTILE,
         "choice" => &AGGR_CHOICE,
         "collect" => &AGGR_COLLECT,
         "shortest" => &AGGR_SHORTEST,
@@ -1217,6 +1264,18 @@ impl Aggregation {
             name if name == AGGR_MIN.name => Box::new(AggrMin::default()),
             name if name == AGGR_MAX.name => Box::new(AggrMax::default()),
             name if name == AGGR_MEAN.name => Box::new(AggrMean::default()),
+            name if name == AGGR_MEDIAN.name => Box::new(AggrQuantile::new("median", 0.5)),
+            name if name == AGGR_PERCENTILE.name => Box::new({
+                let q = args.first().and_then(|a| a.get_float()).ok_or_else(|| {
+                    miette!("'percentile' requires a number between 0 and 1 as its argument")
+                })?;
+                ensure!(
+                    (0. ..=1.).contains(&q),
+                    "argument to 'percentile' must be between 0 and 1, got {}",
+                    q
+                );
+                AggrQuantile::new("percentile", q)
+            }),
             name if name == AGGR_VARIANCE.name => Box::new(AggrVariance::default()),
             name if name == AGGR_STD_DEV.name => Box::new(AggrStdDev::default()),
             name if name == AGGR_CHOICE.name => Box::new(AggrChoice::default()),
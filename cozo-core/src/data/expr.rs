@@ -912,6 +912,7 @@ pub(crate) fn get_op(name: &str) -> Option<&'static Op> {
         "int_range" => &OP_INT_RANGE,
         "rand_float" => &OP_RAND_FLOAT,
         "rand_bernoulli" => &OP_RAND_BERNOULLI,
+        "hash_bernoulli" => &OP_HASH_BERNOULLI,
         "rand_int" => &OP_RAND_INT,
         "rand_choose" => &OP_RAND_CHOOSE,
         "assert" => &OP_ASSERT,
@@ -23,12 +23,14 @@ use miette::{bail, ensure, miette, IntoDiagnostic, Result};
 use num_traits::FloatConst;
 use rand::prelude::*;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use smartstring::SmartString;
 use unicode_normalization::UnicodeNormalization;
 use uuid::v1::Timestamp;
 
 use crate::data::expr::Op;
 use crate::data::json::JsonValue;
+use crate::data::memcmp::MemCmpEncoder;
 use crate::data::relation::VecElementType;
 use crate::data::value::{
     DataValue, JsonData, Num, RegexWrapper, UuidWrapper, Validity, ValidityTs, Vector,
@@ -54,6 +56,7 @@ fn ensure_same_value_type(a: &DataValue, b: &DataValue) -> Result<()> {
             | (Num(_), Num(_))
             | (Str(_), Str(_))
             | (Bytes(_), Bytes(_))
+            | (Uuid(_), Uuid(_))
             | (Regex(_), Regex(_))
             | (List(_), List(_))
             | (Set(_), Set(_))
@@ -385,7 +388,11 @@ pub(crate) fn op_add(args: &[DataValue]) -> Result<DataValue> {
     let mut f_accum = 0.0f64;
     for arg in args {
         match arg {
-            DataValue::Num(Num::Int(i)) => i_accum += i,
+            DataValue::Num(Num::Int(i)) => {
+                i_accum = i_accum
+                    .checked_add(*i)
+                    .ok_or_else(|| miette!("integer overflow in addition"))?
+            }
             DataValue::Num(Num::Float(f)) => f_accum += f,
             DataValue::Vec(_) => return add_vecs(args),
             _ => bail!("addition requires numbers"),
@@ -483,9 +490,10 @@ pub(crate) fn op_min(args: &[DataValue]) -> Result<DataValue> {
 define_op!(OP_SUB, 2, false);
 pub(crate) fn op_sub(args: &[DataValue]) -> Result<DataValue> {
     Ok(match (&args[0], &args[1]) {
-        (DataValue::Num(Num::Int(a)), DataValue::Num(Num::Int(b))) => {
-            DataValue::Num(Num::Int(*a - *b))
-        }
+        (DataValue::Num(Num::Int(a)), DataValue::Num(Num::Int(b))) => DataValue::Num(Num::Int(
+            a.checked_sub(*b)
+                .ok_or_else(|| miette!("integer overflow in subtraction"))?,
+        )),
         (DataValue::Num(Num::Float(a)), DataValue::Num(Num::Float(b))) => {
             DataValue::Num(Num::Float(*a - *b))
         }
@@ -547,7 +555,11 @@ pub(crate) fn op_mul(args: &[DataValue]) -> Result<DataValue> {
     let mut f_accum = 1.0f64;
     for arg in args {
         match arg {
-            DataValue::Num(Num::Int(i)) => i_accum *= i,
+            DataValue::Num(Num::Int(i)) => {
+                i_accum = i_accum
+                    .checked_mul(*i)
+                    .ok_or_else(|| miette!("integer overflow in multiplication"))?
+            }
             DataValue::Num(Num::Float(f)) => f_accum *= f,
             DataValue::Vec(_) => return mul_vecs(args),
             _ => bail!("multiplication requires numbers"),
@@ -614,6 +626,9 @@ fn mul_vecs(args: &[DataValue]) -> Result<DataValue> {
 
 define_op!(OP_DIV, 2, false);
 pub(crate) fn op_div(args: &[DataValue]) -> Result<DataValue> {
+    if args[1].get_float() == Some(0.) {
+        bail!("'div' requires non-zero divisor")
+    }
     Ok(match (&args[0], &args[1]) {
         (DataValue::Num(Num::Int(a)), DataValue::Num(Num::Int(b))) => {
             DataValue::Num(Num::Float((*a as f64) / (*b as f64)))
@@ -670,7 +685,10 @@ pub(crate) fn op_div(args: &[DataValue]) -> Result<DataValue> {
 define_op!(OP_MINUS, 1, false);
 pub(crate) fn op_minus(args: &[DataValue]) -> Result<DataValue> {
     Ok(match &args[0] {
-        DataValue::Num(Num::Int(i)) => DataValue::Num(Num::Int(-(*i))),
+        DataValue::Num(Num::Int(i)) => DataValue::Num(Num::Int(
+            i.checked_neg()
+                .ok_or_else(|| miette!("integer overflow in negation"))?,
+        )),
         DataValue::Num(Num::Float(f)) => DataValue::Num(Num::Float(-(*f))),
         DataValue::Vec(Vector::F64(v)) => DataValue::Vec(Vector::F64(0. - v)),
         DataValue::Vec(Vector::F32(v)) => DataValue::Vec(Vector::F32(0. - v)),
@@ -681,7 +699,10 @@ pub(crate) fn op_minus(args: &[DataValue]) -> Result<DataValue> {
 define_op!(OP_ABS, 1, false);
 pub(crate) fn op_abs(args: &[DataValue]) -> Result<DataValue> {
     Ok(match &args[0] {
-        DataValue::Num(Num::Int(i)) => DataValue::Num(Num::Int(i.abs())),
+        DataValue::Num(Num::Int(i)) => DataValue::Num(Num::Int(
+            i.checked_abs()
+                .ok_or_else(|| miette!("integer overflow in 'abs'"))?,
+        )),
         DataValue::Num(Num::Float(f)) => DataValue::Num(Num::Float(f.abs())),
         DataValue::Vec(Vector::F64(v)) => DataValue::Vec(Vector::F64(v.mapv(|x| x.abs()))),
         DataValue::Vec(Vector::F32(v)) => DataValue::Vec(Vector::F32(v.mapv(|x| x.abs()))),
@@ -1073,7 +1094,10 @@ pub(crate) fn op_mod(args: &[DataValue]) -> Result<DataValue> {
             if *b == 0 {
                 bail!("'mod' requires non-zero divisor")
             }
-            DataValue::Num(Num::Int(a.rem(b)))
+            DataValue::Num(Num::Int(
+                a.checked_rem(*b)
+                    .ok_or_else(|| miette!("integer overflow in 'mod'"))?,
+            ))
         }
         (DataValue::Num(Num::Float(a)), DataValue::Num(Num::Float(b))) => {
             DataValue::Num(Num::Float(a.rem(*b)))
@@ -2323,6 +2347,31 @@ pub(crate) fn op_rand_bernoulli(args: &[DataValue]) -> Result<DataValue> {
     Ok(DataValue::from(thread_rng().gen_bool(prob)))
 }
 
+define_op!(OP_HASH_BERNOULLI, 3, false);
+pub(crate) fn op_hash_bernoulli(args: &[DataValue]) -> Result<DataValue> {
+    let prob = match &args[1] {
+        DataValue::Num(n) => {
+            let f = n.get_float();
+            ensure!(
+                (0. ..=1.).contains(&f),
+                "'hash_bernoulli' requires number between 0. and 1."
+            );
+            f
+        }
+        _ => bail!("'hash_bernoulli' requires number between 0. and 1."),
+    };
+    let seed = args[2]
+        .get_int()
+        .ok_or_else(|| miette!("'hash_bernoulli' requires an integer seed"))?;
+    let mut encoded = seed.to_be_bytes().to_vec();
+    encoded.encode_datavalue(&args[0]);
+    let digest = Sha256::digest(&encoded);
+    let h = u64::from_be_bytes(digest[..8].try_into().unwrap());
+    Ok(DataValue::from(
+        prob >= 1. || (h as f64) < prob * (u64::MAX as f64),
+    ))
+}
+
 define_op!(OP_RAND_INT, 2, false);
 pub(crate) fn op_rand_int(args: &[DataValue]) -> Result<DataValue> {
     let lower = &args[0]
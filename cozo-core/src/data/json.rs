@@ -78,7 +78,7 @@ impl From<DataValue> for JsonValue {
             DataValue::List(l) => {
                 JsonValue::Array(l.iter().map(|v| JsonValue::from(v.clone())).collect())
             }
-            DataValue::Bot => panic!("found bottom"),
+            DataValue::Bot => JsonValue::Null,
             DataValue::Set(l) => {
                 JsonValue::Array(l.iter().map(|v| JsonValue::from(v.clone())).collect())
             }
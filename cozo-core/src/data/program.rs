@@ -52,7 +52,7 @@ pub(crate) struct QueryOutOptions {
     pub(crate) offset: Option<usize>,
     pub(crate) timeout: Option<f64>,
     pub(crate) sleep: Option<f64>,
-    pub(crate) sorters: Vec<(Symbol, SortDir)>,
+    pub(crate) sorters: Vec<Sorter>,
     pub(crate) store_relation: Option<(InputRelationHandle, RelationOp, ReturnMutation)>,
     pub(crate) assertion: Option<QueryAssertion>,
 }
@@ -74,12 +74,31 @@ impl Display for QueryOutOptions {
         if let Some(l) = self.timeout {
             writeln!(f, ":timeout {l};")?;
         }
-        for (symb, dir) in &self.sorters {
+        for Sorter {
+            key,
+            expr,
+            dir,
+            nulls,
+            collate,
+        } in &self.sorters
+        {
             write!(f, ":order ")?;
             if *dir == SortDir::Dsc {
                 write!(f, "-")?;
             }
-            writeln!(f, "{symb};")?;
+            match expr {
+                None => write!(f, "{key}")?,
+                Some(expr) => write!(f, "({expr})")?,
+            }
+            if *collate == SortCollate::NoCase {
+                write!(f, " collate nocase")?;
+            }
+            match nulls {
+                SortNulls::Natural => {}
+                SortNulls::First => write!(f, " nulls first")?,
+                SortNulls::Last => write!(f, " nulls last")?,
+            }
+            writeln!(f, ";")?;
         }
         if let Some((
                         InputRelationHandle {
@@ -189,6 +208,36 @@ pub(crate) enum SortDir {
     Dsc,
 }
 
+/// Placement of nulls in a sorted column.
+/// `Natural` keeps the ordering of `DataValue`, where null is the smallest value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SortNulls {
+    Natural,
+    First,
+    Last,
+}
+
+/// How strings in a sorted column are compared.
+/// `NoCase` compares the lowercased forms and `Unicode` the forms with accents and case
+/// folded away, leaving the returned values untouched.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SortCollate {
+    Binary,
+    NoCase,
+    Unicode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Sorter {
+    pub(crate) key: Symbol,
+    /// A computed sort key over the output columns, written in parentheses.
+    /// When present, `key` only carries its source text and span for messages.
+    pub(crate) expr: Option<Expr>,
+    pub(crate) dir: SortDir,
+    pub(crate) nulls: SortNulls,
+    pub(crate) collate: SortCollate,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) enum RelationOp {
     Create,
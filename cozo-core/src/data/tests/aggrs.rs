@@ -9,7 +9,7 @@
 use approx::AbsDiffEq;
 use itertools::Itertools;
 
-use crate::data::aggr::parse_aggr;
+use crate::data::aggr::{parse_aggr, AggrCount, NormalAggrObj};
 use crate::data::value::DataValue;
 
 #[test]
@@ -248,6 +248,18 @@ fn test_count() {
     assert_eq!(count_aggr.get().unwrap(), DataValue::from(6));
 }
 
+#[test]
+fn test_count_saturates() {
+    let mut count_aggr = AggrCount {
+        count: i64::MAX - 1,
+    };
+    count_aggr.set(&DataValue::Null).unwrap();
+    assert_eq!(count_aggr.get().unwrap(), DataValue::from(i64::MAX));
+    count_aggr.set(&DataValue::Null).unwrap();
+    count_aggr.set(&DataValue::Null).unwrap();
+    assert_eq!(count_aggr.get().unwrap(), DataValue::from(i64::MAX));
+}
+
 #[test]
 fn test_variance() {
     let mut aggr = parse_aggr("variance").unwrap().clone();
@@ -572,3 +584,41 @@ fn test_bit_xor() {
     bit_xor_aggr.set(&DataValue::Bytes(vec![0b01011])).unwrap();
     assert_eq!(bit_xor_aggr.get().unwrap(), DataValue::Bytes(vec![0b10111]));
 }
+
+#[test]
+fn test_median_and_percentile() {
+    let quantile = |name: &str, args: &[DataValue], values: &[f64]| {
+        let mut aggr = parse_aggr(name).unwrap().clone();
+        aggr.normal_init(args).unwrap();
+        let mut op = aggr.normal_op.unwrap();
+        for v in values {
+            op.set(&DataValue::from(*v)).unwrap();
+        }
+        op.get().unwrap()
+    };
+    assert_eq!(
+        quantile("median", &[], &[5., 1., 3., 2., 4.]),
+        DataValue::from(3.)
+    );
+    assert_eq!(
+        quantile("median", &[], &[4., 1., 3., 2.]),
+        DataValue::from(2.5)
+    );
+    assert_eq!(quantile("median", &[], &[]), DataValue::Null);
+    let hundred = (1..=100).map(|i| i as f64).collect::<Vec<_>>();
+    let p95 = quantile("percentile", &[DataValue::from(0.95)], &hundred)
+        .get_float()
+        .unwrap();
+    assert!(p95.abs_diff_eq(&95.05, 1e-10));
+    assert_eq!(
+        quantile("percentile", &[DataValue::from(1)], &hundred),
+        DataValue::from(100.)
+    );
+
+    let mut aggr = parse_aggr("percentile").unwrap().clone();
+    assert!(aggr.normal_init(&[DataValue::from(1.5)]).is_err());
+    assert!(aggr.normal_init(&[]).is_err());
+    let mut aggr = parse_aggr("median").unwrap().clone();
+    aggr.normal_init(&[]).unwrap();
+    assert!(aggr.normal_op.unwrap().set(&DataValue::from("a")).is_err());
+}
@@ -66,6 +66,34 @@ fn test_mul() {
     );
 }
 
+#[test]
+fn test_int_overflow() {
+    let max = DataValue::from(i64::MAX);
+    let min = DataValue::from(i64::MIN);
+    assert!(op_add(&[max.clone(), DataValue::from(1)]).is_err());
+    assert!(op_add(&[min.clone(), DataValue::from(-1)]).is_err());
+    assert_eq!(
+        op_add(&[max.clone(), DataValue::from(-1), DataValue::from(1)]).unwrap(),
+        max
+    );
+    assert!(op_sub(&[min.clone(), DataValue::from(1)]).is_err());
+    assert!(op_mul(&[max.clone(), DataValue::from(2)]).is_err());
+    assert!(op_minus(&[DataValue::from(i64::MIN)]).is_err());
+    assert!(op_abs(&[DataValue::from(i64::MIN)]).is_err());
+    assert!(op_mod(&[min.clone(), DataValue::from(-1)]).is_err());
+    assert_eq!(
+        op_mod(&[min.clone(), DataValue::from(2)]).unwrap(),
+        DataValue::from(0)
+    );
+    assert!(DbInstance::default()
+        .run_default("?[x] := x = mod(-9223372036854775807 - 1, -1)")
+        .is_err());
+    assert_eq!(
+        op_add(&[max, DataValue::from(1.0)]).unwrap(),
+        DataValue::from(i64::MAX as f64 + 1.0)
+    );
+}
+
 #[test]
 fn test_div() {
     assert_eq!(
@@ -80,7 +108,10 @@ fn test_div() {
         op_div(&[DataValue::from(7.0), DataValue::from(0.5)]).unwrap(),
         DataValue::from(14.0)
     );
-    assert!(op_div(&[DataValue::from(1), DataValue::from(0)]).is_ok());
+    assert!(op_div(&[DataValue::from(1), DataValue::from(0)]).is_err());
+    assert!(op_div(&[DataValue::from(1.0), DataValue::from(0)]).is_err());
+    assert!(op_div(&[DataValue::from(1), DataValue::from(0.0)]).is_err());
+    assert!(op_div(&[DataValue::from(0.0), DataValue::from(-0.0)]).is_err());
 }
 
 #[test]
@@ -1321,6 +1352,40 @@ fn test_to_float() {
     );
 }
 
+#[test]
+fn test_hash_bernoulli() {
+    let sample = |key: i64, p: f64, seed: i64| {
+        op_hash_bernoulli(&[
+            DataValue::from(key),
+            DataValue::from(p),
+            DataValue::from(seed),
+        ])
+        .unwrap()
+        .get_bool()
+        .unwrap()
+    };
+    let picked = |seed| {
+        (0..10000)
+            .filter(|k| sample(*k, 0.1, seed))
+            .collect::<Vec<_>>()
+    };
+    let with_seed_1 = picked(1);
+    assert_eq!(with_seed_1, picked(1));
+    assert_ne!(with_seed_1, picked(2));
+    assert!((800..1200).contains(&with_seed_1.len()));
+    assert!((0..100).all(|k| sample(k, 1., 7)));
+    assert!((0..100).all(|k| !sample(k, 0., 7)));
+    assert!(
+        op_hash_bernoulli(&[DataValue::from(1), DataValue::from(2), DataValue::from(0)]).is_err()
+    );
+    assert!(op_hash_bernoulli(&[
+        DataValue::from(1),
+        DataValue::from(0.5),
+        DataValue::from("s")
+    ])
+    .is_err());
+}
+
 #[test]
 fn test_rand() {
     let n = op_rand_float(&[]).unwrap().get_float().unwrap();
@@ -7,10 +7,16 @@
  *
  */
 
+use std::collections::BTreeSet;
+
+use ndarray::Array1;
+use regex::Regex;
 use serde_json::json;
+use uuid::Uuid;
 
 use crate::data::json::JsonValue;
-use crate::data::value::DataValue;
+use crate::data::value::{DataValue, RegexWrapper, UuidWrapper, Validity, Vector};
+use crate::JsonData;
 
 #[test]
 fn bad_values() {
@@ -19,3 +25,61 @@ fn bad_values() {
     println!("{}", JsonValue::from(DataValue::from(f64::NEG_INFINITY)));
     println!("{}", JsonValue::from(DataValue::from(f64::NAN)));
 }
+
+#[test]
+fn json_of_each_variant() {
+    let native = [
+        (DataValue::Null, json!(null)),
+        (DataValue::from(true), json!(true)),
+        (DataValue::from(-3), json!(-3)),
+        (DataValue::from(2.5), json!(2.5)),
+        (DataValue::from("hi"), json!("hi")),
+        (
+            DataValue::List(vec![DataValue::from(1), DataValue::from("a")]),
+            json!([1, "a"]),
+        ),
+        (
+            DataValue::Json(JsonData(json!({"a": [1, 2]}))),
+            json!({"a": [1, 2]}),
+        ),
+    ];
+    for (val, expected) in native {
+        let j = JsonValue::from(val.clone());
+        assert_eq!(j, expected);
+        assert_eq!(DataValue::from(j), val);
+    }
+
+    let uuid = Uuid::parse_str("dd85b19a-5fde-11ed-a88e-1774a7698039").unwrap();
+    let lossy = [
+        (DataValue::Bytes(vec![1, 2, 3]), json!("AQID")),
+        (
+            DataValue::Uuid(UuidWrapper(uuid)),
+            json!("dd85b19a-5fde-11ed-a88e-1774a7698039"),
+        ),
+        (
+            DataValue::Set(BTreeSet::from([DataValue::from(2), DataValue::from(1)])),
+            json!([1, 2]),
+        ),
+        (
+            DataValue::Validity(Validity::from((10, true))),
+            json!([10, true]),
+        ),
+        (DataValue::from(f64::INFINITY), json!("INFINITY")),
+        (
+            DataValue::Regex(RegexWrapper(Regex::new("^a+$").unwrap())),
+            json!("^a+$"),
+        ),
+        (
+            DataValue::Vec(Vector::F32(Array1::from(vec![0.5, 1.]))),
+            json!([0.5, 1.0]),
+        ),
+        (
+            DataValue::Vec(Vector::F64(Array1::from(vec![0.25, -2.]))),
+            json!([0.25, -2.0]),
+        ),
+        (DataValue::Bot, json!(null)),
+    ];
+    for (val, expected) in lossy {
+        assert_eq!(JsonValue::from(val), expected);
+    }
+}
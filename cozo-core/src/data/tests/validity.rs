@@ -194,3 +194,112 @@ fn test_validity() {
 
     println!("{}", json!(res));
 }
+
+#[test]
+fn test_validity_as_of() {
+    let db = DbInstance::default();
+    db.run_default(":create status {k, vld: Validity => s}")
+        .unwrap();
+    db.run_default(
+        r#"
+        ?[k, vld, s] <- [[1, [10, true], 'on'], [1, [20, false], 'on'], [1, [30, true], 'on']]
+        :put status {k, vld => s}
+    "#,
+    )
+    .unwrap();
+    for (ts, expected) in [(5, 0), (10, 1), (25, 0), (30, 1), (100, 1)] {
+        let res = db
+            .run_default(&format!("?[k, s] := *status{{k, s @ {ts}}}"))
+            .unwrap()
+            .rows;
+        assert_eq!(res.len(), expected, "as of {ts}");
+    }
+    let res = db
+        .run_default("?[k, then, now] := *status{k, s: then @ 25}, *status{k, s: now @ 'NOW'}")
+        .unwrap()
+        .rows;
+    assert!(res.is_empty());
+    let res = db
+        .run_default("?[k, then, now] := *status{k, s: then @ 15}, *status{k, s: now @ 'NOW'}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, "on", "on"]]));
+}
+
+#[test]
+fn test_bind_validity() {
+    let now_micros = || {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as i64
+    };
+    let db = DbInstance::default();
+    db.run_default(":create price {item, vld: Validity => p}")
+        .unwrap();
+    db.run_default("?[item, vld, p] <- [['tea', [100, true], 3]] :put price {item, vld => p}")
+        .unwrap();
+    let before = now_micros();
+    db.run_default("?[item, vld, p] <- [['tea', 'ASSERT', 4]] :put price {item, vld => p}")
+        .unwrap();
+    let after = now_micros();
+    let res = db
+        .run_default(
+            "?[p, ts, asserted] := *price{item: 'tea', vld, p}, ts = to_int(vld), asserted = to_bool(vld) :order ts",
+        )
+        .unwrap()
+        .rows;
+    assert_eq!(res.len(), 2);
+    assert_eq!(
+        res[0],
+        vec![
+            DataValue::from(3),
+            DataValue::from(100),
+            DataValue::from(true)
+        ]
+    );
+    assert_eq!(res[1][0], DataValue::from(4));
+    let ts = res[1][1].get_int().unwrap();
+    assert!(before <= ts && ts <= after, "{before} <= {ts} <= {after}");
+}
+
+#[test]
+fn test_diff_between_validities() {
+    let db = DbInstance::default();
+    db.run_default(":create email {id, vld: Validity => addr}")
+        .unwrap();
+    db.run_default(
+        r#"
+        ?[id, vld, addr] <- [
+            [1, [10, true], 'a@old.com'],
+            [2, [10, true], 'b@x.com'],
+            [1, [20, true], 'a@new.com'],
+            [2, [20, false], 'b@x.com'],
+            [3, [20, true], 'c@x.com'],
+            [4, [10, true], 'd@x.com']
+        ]
+        :put email {id, vld => addr}
+    "#,
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            before[id, addr] := *email{id, addr @ 15}
+            after[id, addr] := *email{id, addr @ 25}
+            ?[id, old, new, kind] := after[id, new], not before[id, _], old = null, kind = 'added'
+            ?[id, old, new, kind] := before[id, old], not after[id, _], new = null, kind = 'removed'
+            ?[id, old, new, kind] := before[id, old], after[id, new], old != new, kind = 'changed'
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            [1, "a@old.com", "a@new.com", "changed"],
+            [2, "b@x.com", null, "removed"],
+            [3, null, "c@x.com", "added"]
+        ])
+    );
+}
@@ -55,3 +55,32 @@ fn display_datavalues() {
         ])
     );
 }
+
+#[test]
+fn mixed_int_float_order() {
+    let mut vals = vec![
+        DataValue::from(3.0),
+        DataValue::from(-1),
+        DataValue::from(3),
+        DataValue::from(2.5),
+        DataValue::from(-1.0),
+        DataValue::from(f64::NEG_INFINITY),
+        DataValue::from(i64::MAX),
+    ];
+    vals.sort();
+    assert_eq!(
+        vals,
+        vec![
+            DataValue::from(f64::NEG_INFINITY),
+            DataValue::from(-1),
+            DataValue::from(-1.0),
+            DataValue::from(2.5),
+            DataValue::from(3),
+            DataValue::from(3.0),
+            DataValue::from(i64::MAX),
+        ]
+    );
+    assert!(DataValue::from(3) < DataValue::from(3.0));
+    assert!(DataValue::from(3.0) > DataValue::from(3));
+    assert_ne!(DataValue::from(3), DataValue::from(3.0));
+}
@@ -7,11 +7,12 @@
  */
 
 use crate::data::functions::TERMINAL_VALIDITY;
-use miette::Result;
+use miette::{Diagnostic, Result, WrapErr};
 use std::cmp::Reverse;
+use thiserror::Error;
 
 use crate::data::memcmp::MemCmpEncoder;
-use crate::data::value::{DataValue, Validity, ValidityTs};
+use crate::data::value::{DataValue, Num, Validity, ValidityTs};
 use crate::runtime::relation::RelationId;
 
 pub type Tuple = Vec<DataValue>;
@@ -84,3 +85,120 @@ pub fn check_key_for_validity(key: &[u8], valid_at: ValidityTs, size_hint: Optio
 }
 
 pub(crate) const ENCODED_KEY_MIN_LEN: usize = 8;
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Expected {0}, got {1:?}")]
+#[diagnostic(code(eval::value_type_mismatch))]
+struct ValueTypeMismatch(&'static str, DataValue);
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Expected a row of {0} columns, got {1}")]
+#[diagnostic(code(eval::row_arity_mismatch))]
+struct RowArityMismatch(usize, usize);
+
+/// Conversion from a single [DataValue] into a Rust value.
+pub trait FromDataValue: Sized {
+    /// Convert the value, failing if it has the wrong type.
+    fn from_data_value(value: &DataValue) -> Result<Self>;
+}
+
+impl FromDataValue for DataValue {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+/// Only integers convert; a float is rejected even if it is whole.
+impl FromDataValue for i64 {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        match value {
+            DataValue::Num(Num::Int(i)) => Ok(*i),
+            v => Err(ValueTypeMismatch("an integer", v.clone()).into()),
+        }
+    }
+}
+
+impl FromDataValue for f64 {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        value
+            .get_float()
+            .ok_or_else(|| ValueTypeMismatch("a number", value.clone()).into())
+    }
+}
+
+impl FromDataValue for bool {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        value
+            .get_bool()
+            .ok_or_else(|| ValueTypeMismatch("a boolean", value.clone()).into())
+    }
+}
+
+impl FromDataValue for String {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        value
+            .get_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| ValueTypeMismatch("a string", value.clone()).into())
+    }
+}
+
+impl FromDataValue for Vec<u8> {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        value
+            .get_bytes()
+            .map(|b| b.to_vec())
+            .ok_or_else(|| ValueTypeMismatch("bytes", value.clone()).into())
+    }
+}
+
+/// `null` converts to `None`, anything else is converted as `T`.
+impl<T: FromDataValue> FromDataValue for Option<T> {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        match value {
+            DataValue::Null => Ok(None),
+            v => T::from_data_value(v).map(Some),
+        }
+    }
+}
+
+impl<T: FromDataValue> FromDataValue for Vec<T> {
+    fn from_data_value(value: &DataValue) -> Result<Self> {
+        match value.get_slice() {
+            Some(l) => l.iter().map(T::from_data_value).collect(),
+            None => Err(ValueTypeMismatch("a list", value.clone()).into()),
+        }
+    }
+}
+
+/// Conversion from a whole row into a Rust value, usually a tuple of [FromDataValue] types
+/// in the order of the output columns.
+pub trait FromTuple: Sized {
+    /// Convert the row, failing if its length or the type of any column is wrong.
+    fn from_tuple(tuple: &[DataValue]) -> Result<Self>;
+}
+
+macro_rules! impl_from_tuple {
+    ($len:expr; $($t:ident $idx:tt),+) => {
+        impl<$($t: FromDataValue),+> FromTuple for ($($t,)+) {
+            fn from_tuple(tuple: &[DataValue]) -> Result<Self> {
+                if tuple.len() != $len {
+                    return Err(RowArityMismatch($len, tuple.len()).into());
+                }
+                Ok(($(
+                    $t::from_data_value(&tuple[$idx])
+                        .wrap_err_with(|| format!("when converting column {}", $idx))?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_from_tuple!(1; A 0);
+impl_from_tuple!(2; A 0, B 1);
+impl_from_tuple!(3; A 0, B 1, C 2);
+impl_from_tuple!(4; A 0, B 1, C 2, D 3);
+impl_from_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_from_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_tuple!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_tuple!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
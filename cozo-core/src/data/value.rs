@@ -139,7 +139,11 @@ impl From<(i64, bool)> for Validity {
     }
 }
 
-/// A Value in the database
+/// A Value in the database.
+///
+/// Values of different variants are ordered by variant, in the order declared below
+/// (null < bool < number < string < bytes < uuid < ... < list < ...), so a column
+/// holding mixed types still sorts deterministically.
 #[derive(
 Clone, PartialEq, Eq, PartialOrd, Ord, serde_derive::Deserialize, serde_derive::Serialize, Hash,
 )]
@@ -551,6 +555,8 @@ impl PartialOrd for Num {
     }
 }
 
+/// Integers and floats compare by numeric value; an integer sorts just before
+/// a float of equal value, so the order stays total across both.
 impl Ord for Num {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -678,6 +684,19 @@ impl DataValue {
             _ => None,
         }
     }
+    /// Approximate number of bytes the value occupies in memory, including heap data.
+    pub(crate) fn approx_size(&self) -> usize {
+        let heap = match self {
+            DataValue::Str(s) => s.len(),
+            DataValue::Bytes(b) => b.len(),
+            DataValue::List(l) => l.iter().map(|v| v.approx_size()).sum(),
+            DataValue::Set(s) => s.iter().map(|v| v.approx_size()).sum(),
+            DataValue::Vec(Vector::F32(a)) => std::mem::size_of_val(a.as_slice().unwrap_or(&[])),
+            DataValue::Vec(Vector::F64(a)) => std::mem::size_of_val(a.as_slice().unwrap_or(&[])),
+            _ => 0,
+        };
+        std::mem::size_of::<DataValue>() + heap
+    }
     pub(crate) fn uuid(uuid: Uuid) -> Self {
         Self::Uuid(UuidWrapper(uuid))
     }
@@ -47,6 +47,7 @@ use miette::{
 };
 use serde_json::json;
 
+pub use data::tuple::{FromDataValue, FromTuple};
 pub use data::value::{DataValue, Num, RegexWrapper, UuidWrapper, Validity, ValidityTs};
 pub use fixed_rule::{FixedRule, FixedRuleInputRelation, FixedRulePayload};
 pub use runtime::db::Db;
@@ -74,6 +75,7 @@ pub use crate::runtime::callback::CallbackOp;
 pub use crate::runtime::db::evaluate_expressions;
 pub use crate::runtime::db::get_variables;
 pub use crate::runtime::db::Poison;
+pub use crate::runtime::db::QueryLimits;
 pub use crate::runtime::db::ScriptMutability;
 pub use crate::runtime::db::TransactionPayload;
 
@@ -480,6 +482,63 @@ impl DbInstance {
         }
     }
 
+    /// Dispatcher method. See [crate::Db::set_query_limits].
+    pub fn set_query_limits(&self, limits: QueryLimits) {
+        match self {
+            DbInstance::Mem(db) => db.set_query_limits(limits),
+            #[cfg(feature = "storage-sqlite")]
+            DbInstance::Sqlite(db) => db.set_query_limits(limits),
+            #[cfg(feature = "storage-rocksdb")]
+            DbInstance::RocksDb(db) => db.set_query_limits(limits),
+            #[cfg(feature = "storage-sled")]
+            DbInstance::Sled(db) => db.set_query_limits(limits),
+            #[cfg(feature = "storage-tikv")]
+            DbInstance::TiKv(db) => db.set_query_limits(limits),
+        }
+    }
+    /// Dispatcher method. See [crate::Db::query_limits].
+    pub fn query_limits(&self) -> QueryLimits {
+        match self {
+            DbInstance::Mem(db) => db.query_limits(),
+            #[cfg(feature = "storage-sqlite")]
+            DbInstance::Sqlite(db) => db.query_limits(),
+            #[cfg(feature = "storage-rocksdb")]
+            DbInstance::RocksDb(db) => db.query_limits(),
+            #[cfg(feature = "storage-sled")]
+            DbInstance::Sled(db) => db.query_limits(),
+            #[cfg(feature = "storage-tikv")]
+            DbInstance::TiKv(db) => db.query_limits(),
+        }
+    }
+    /// Dispatcher method. See [crate::Db::set_memory_accounting].
+    pub fn set_memory_accounting(&self, enabled: bool) {
+        match self {
+            DbInstance::Mem(db) => db.set_memory_accounting(enabled),
+            #[cfg(feature = "storage-sqlite")]
+            DbInstance::Sqlite(db) => db.set_memory_accounting(enabled),
+            #[cfg(feature = "storage-rocksdb")]
+            DbInstance::RocksDb(db) => db.set_memory_accounting(enabled),
+            #[cfg(feature = "storage-sled")]
+            DbInstance::Sled(db) => db.set_memory_accounting(enabled),
+            #[cfg(feature = "storage-tikv")]
+            DbInstance::TiKv(db) => db.set_memory_accounting(enabled),
+        }
+    }
+    /// Dispatcher method. See [crate::Db::temp_bytes].
+    pub fn temp_bytes(&self) -> usize {
+        match self {
+            DbInstance::Mem(db) => db.temp_bytes(),
+            #[cfg(feature = "storage-sqlite")]
+            DbInstance::Sqlite(db) => db.temp_bytes(),
+            #[cfg(feature = "storage-rocksdb")]
+            DbInstance::RocksDb(db) => db.temp_bytes(),
+            #[cfg(feature = "storage-sled")]
+            DbInstance::Sled(db) => db.temp_bytes(),
+            #[cfg(feature = "storage-tikv")]
+            DbInstance::TiKv(db) => db.temp_bytes(),
+        }
+    }
+
     /// Dispatcher method. See [crate::Db::run_multi_transaction]
     pub fn run_multi_transaction(
         &self,
@@ -514,6 +573,21 @@ impl DbInstance {
             receiver: db2app_recv,
         }
     }
+    /// Runs several read-only scripts in a single read transaction, so that all of them see
+    /// the same snapshot. Each script gets its own result, and a failing script does not stop
+    /// the ones after it. A script that writes to a stored relation fails with an error.
+    pub fn run_batch_read_only(
+        &self,
+        scripts: Vec<(String, BTreeMap<String, DataValue>)>,
+    ) -> Vec<Result<NamedRows>> {
+        let tx = self.multi_transaction(false);
+        let results = scripts
+            .into_iter()
+            .map(|(payload, params)| tx.run_script(&payload, params))
+            .collect();
+        let _ = tx.abort();
+        results
+    }
 }
 
 /// A multi-transaction handle.
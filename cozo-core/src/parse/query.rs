@@ -26,7 +26,8 @@ use crate::data::functions::{str2vld, MAX_VALIDITY_TS};
 use crate::data::program::{
     FixedRuleApply, FixedRuleArg, InputAtom, InputInlineRule, InputInlineRulesOrFixed,
     InputNamedFieldRelationApplyAtom, InputProgram, InputRelationApplyAtom, InputRuleApplyAtom,
-    QueryAssertion, QueryOutOptions, RelationOp, ReturnMutation, SearchInput, SortDir, Unification,
+    QueryAssertion, QueryOutOptions, RelationOp, ReturnMutation, SearchInput, SortCollate, SortDir,
+    SortNulls, Sorter, Unification,
 };
 use crate::data::relation::{ColType, ColumnDef, NullableColType, StoredRelationMetadata};
 use crate::data::symb::{Symbol, PROG_ENTRY};
@@ -211,6 +212,19 @@ pub(crate) fn parse_query(
                 for (a, v) in aggr.iter().zip(head.iter()) {
                     ensure!(a.is_none(), AggrInConstRuleError(v.span));
                 }
+
+                #[derive(Debug, Error, Diagnostic)]
+                #[error("Constant rule names the column '{0}' more than once")]
+                #[diagnostic(code(parser::dup_binding_in_const_rule))]
+                struct DuplicateBindingInConstRuleError(String, #[label] SourceSpan);
+
+                let mut seen = BTreeSet::new();
+                for v in head.iter() {
+                    ensure!(
+                        seen.insert(v),
+                        DuplicateBindingInConstRuleError(v.name.to_string(), v.span)
+                    );
+                }
                 let data_part = src.next().unwrap();
                 let data_part_str = data_part.as_str();
                 let data = build_expr(data_part.clone(), param_pool)?;
@@ -311,7 +325,10 @@ pub(crate) fn parse_query(
             Rule::sort_option => {
                 for part in pair.into_inner() {
                     let mut var = "";
+                    let mut expr = None;
                     let mut dir = SortDir::Asc;
+                    let mut nulls = SortNulls::Natural;
+                    let mut collate = SortCollate::Binary;
                     let mut span = part.extract_span();
                     for a in part.into_inner() {
                         match a.as_rule() {
@@ -319,12 +336,28 @@ pub(crate) fn parse_query(
                                 var = a.as_str();
                                 span = a.extract_span();
                             }
+                            Rule::grouping => {
+                                var = a.as_str();
+                                span = a.extract_span();
+                                expr =
+                                    Some(build_expr(a.into_inner().next().unwrap(), param_pool)?);
+                            }
                             Rule::sort_asc => dir = SortDir::Asc,
                             Rule::sort_desc => dir = SortDir::Dsc,
+                            Rule::sort_nulls_first => nulls = SortNulls::First,
+                            Rule::sort_nulls_last => nulls = SortNulls::Last,
+                            Rule::sort_collate_nocase => collate = SortCollate::NoCase,
+                            Rule::sort_collate_unicode => collate = SortCollate::Unicode,
                             _ => unreachable!(),
                         }
                     }
-                    out_opts.sorters.push((Symbol::new(var, span), dir));
+                    out_opts.sorters.push(Sorter {
+                        key: Symbol::new(var, span),
+                        expr,
+                        dir,
+                        nulls,
+                        collate,
+                    });
                 }
             }
             Rule::returning_option => {
@@ -477,11 +510,21 @@ pub(crate) fn parse_query(
 
         let head_args = prog.get_entry_out_head()?;
 
-        for (sorter, _) in &prog.out_opts.sorters {
-            ensure!(
-                head_args.contains(sorter),
-                SortKeyNotFound(sorter.to_string(), sorter.span)
-            )
+        for Sorter { key, expr, .. } in &prog.out_opts.sorters {
+            match expr {
+                None => ensure!(
+                    head_args.contains(key),
+                    SortKeyNotFound(key.to_string(), key.span)
+                ),
+                Some(expr) => {
+                    for var in expr.bindings()? {
+                        ensure!(
+                            head_args.contains(&var),
+                            SortKeyNotFound(var.to_string(), var.span)
+                        )
+                    }
+                }
+            }
         }
     }
 
@@ -615,12 +615,6 @@ impl<'a> SessionTx<'a> {
 
         let ret_vars_set = ret_vars.iter().cloned().collect();
         ret.eliminate_temp_vars(&ret_vars_set)?;
-        let cur_ret_set: BTreeSet<_> = ret.bindings_after_eliminate().into_iter().collect();
-        if cur_ret_set != ret_vars_set {
-            let ret_span = ret.span();
-            ret = ret.cartesian_join(RelAlgebra::unit(ret_span), ret_span);
-            ret.eliminate_temp_vars(&ret_vars_set)?;
-        }
 
         let cur_ret_set: BTreeSet<_> = ret.bindings_after_eliminate().into_iter().collect();
         #[derive(Debug, Error, Diagnostic)]
@@ -631,12 +625,14 @@ impl<'a> SessionTx<'a> {
         ))]
         struct UnboundSymbolInRuleHead(String, #[label] SourceSpan);
 
-        ensure!(cur_ret_set == ret_vars_set, {
+        ensure!(ret_vars_set.is_subset(&cur_ret_set), {
             let unbound = ret_vars_set.difference(&cur_ret_set).next().unwrap();
             UnboundSymbolInRuleHead(unbound.to_string(), unbound.span)
         });
         let cur_ret_bindings = ret.bindings_after_eliminate();
         if ret_vars != cur_ret_bindings {
+            // reordering onto the head also drops any bindings the relation
+            // could not eliminate by itself
             ret = ret.reorder(ret_vars.to_vec());
         }
 
@@ -12,9 +12,10 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use itertools::Itertools;
 use log::{debug, trace};
-use miette::Result;
+use miette::{bail, Diagnostic, Result};
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
+use thiserror::Error;
 
 use crate::data::aggr::Aggregation;
 use crate::data::program::{MagicSymbol, NoEntryError};
@@ -68,8 +69,11 @@ impl<'a> SessionTx<'a> {
         store_lifetimes: BTreeMap<MagicSymbol, usize>,
         total_num_to_take: Option<usize>,
         num_to_skip: Option<usize>,
+        max_temp_bytes: Option<usize>,
+        account_temp_bytes: bool,
         poison: Poison,
     ) -> Result<(EpochStore, bool)> {
+        let temp_bytes_at_start = self.temp_bytes();
         let mut stores: BTreeMap<MagicSymbol, EpochStore> = BTreeMap::new();
         let mut early_return = false;
         for (stratum, cur_prog) in strata.iter().enumerate() {
@@ -100,6 +104,8 @@ impl<'a> SessionTx<'a> {
                 &mut stores,
                 total_num_to_take,
                 num_to_skip,
+                (account_temp_bytes || max_temp_bytes.is_some())
+                    .then_some((temp_bytes_at_start, max_temp_bytes)),
                 poison.clone(),
             )?;
         }
@@ -116,8 +122,14 @@ impl<'a> SessionTx<'a> {
         stores: &mut BTreeMap<MagicSymbol, EpochStore>,
         total_num_to_take: Option<usize>,
         num_to_skip: Option<usize>,
+        temp_bytes_counter: Option<(usize, Option<usize>)>,
         poison: Poison,
     ) -> Result<bool> {
+        #[derive(Debug, Error, Diagnostic)]
+        #[error("Query materialized about {0} bytes of temporary data, more than the limit of {1}")]
+        #[diagnostic(code(eval::temp_memory_exceeded))]
+        struct TempMemoryExceeded(usize, usize);
+
         let limiter = QueryLimiter {
             total: total_num_to_take,
             skip: num_to_skip,
@@ -290,6 +302,16 @@ impl<'a> SessionTx<'a> {
             }
             let mut changed = false;
             for (k, new_store) in to_merge {
+                // sizing the store walks every tuple, so it is only done when asked for
+                if let Some((at_start, max)) = temp_bytes_counter {
+                    let added = new_store.approx_bytes();
+                    let total = self.temp_bytes.fetch_add(added, Ordering::Relaxed) + added;
+                    if let Some(max) = max {
+                        if total - at_start > max {
+                            bail!(TempMemoryExceeded(total - at_start, max))
+                        }
+                    }
+                }
                 let old_store = stores.get_mut(k).unwrap();
                 old_store.merge_in(new_store)?;
                 trace!("delta for {}: {}", k, old_store.has_delta());
@@ -62,6 +62,87 @@ impl RelAlgebra {
             RelAlgebra::LshSearch(i) => i.lsh_search.span,
         }
     }
+
+    /// Finds a stored relation that evaluation would have to scan in full:
+    /// one joined on no key prefix and without a bound on its first key,
+    /// or one that must be materialized for a join.
+    pub(crate) fn find_full_scan(&self) -> Option<(&str, SourceSpan)> {
+        fn check_right<'a>(
+            left: &RelAlgebra,
+            right: &'a RelAlgebra,
+            joiner: &Joiner,
+        ) -> Option<(&'a str, SourceSpan)> {
+            let (bindings, storage, filters, span) = match right {
+                RelAlgebra::Stored(r) => (&r.bindings, &r.storage, &r.filters, r.span),
+                RelAlgebra::StoredWithValidity(r) => (&r.bindings, &r.storage, &r.filters, r.span),
+                r => return r.find_full_scan(),
+            };
+            let (_, right_indices) = joiner
+                .join_indices(&left.bindings_after_eliminate(), bindings)
+                .ok()?;
+            let is_full_scan = if !join_is_prefix(&right_indices) {
+                true
+            } else if right_indices.is_empty() {
+                match compute_bounds(filters, bindings) {
+                    Ok((l_bound, u_bound)) => {
+                        l_bound.first() == Some(&DataValue::Null)
+                            && u_bound.first() == Some(&DataValue::Bot)
+                    }
+                    Err(_) => true,
+                }
+            } else {
+                false
+            };
+            if is_full_scan {
+                Some((&storage.name, span))
+            } else {
+                None
+            }
+        }
+
+        match self {
+            RelAlgebra::Fixed(_) | RelAlgebra::TempStore(_) => None,
+            RelAlgebra::Stored(r) => Some((&r.storage.name, r.span)),
+            RelAlgebra::StoredWithValidity(r) => Some((&r.storage.name, r.span)),
+            RelAlgebra::Join(j) => j
+                .left
+                .find_full_scan()
+                .or_else(|| check_right(&j.left, &j.right, &j.joiner)),
+            RelAlgebra::NegJoin(j) => j
+                .left
+                .find_full_scan()
+                .or_else(|| check_right(&j.left, &j.right, &j.joiner)),
+            RelAlgebra::Reorder(r) => r.relation.find_full_scan(),
+            RelAlgebra::Filter(r) => r.parent.find_full_scan(),
+            RelAlgebra::Unification(r) => r.parent.find_full_scan(),
+            RelAlgebra::HnswSearch(r) => r.parent.find_full_scan(),
+            RelAlgebra::FtsSearch(r) => r.parent.find_full_scan(),
+            RelAlgebra::LshSearch(r) => r.parent.find_full_scan(),
+        }
+    }
+
+    /// Counts the joins evaluation would perform. A join whose left side is
+    /// the unit relation is how the first atom of a rule enters the plan,
+    /// so it is not counted.
+    pub(crate) fn count_joins(&self) -> usize {
+        match self {
+            RelAlgebra::Fixed(_)
+            | RelAlgebra::TempStore(_)
+            | RelAlgebra::Stored(_)
+            | RelAlgebra::StoredWithValidity(_) => 0,
+            RelAlgebra::Join(j) => {
+                let own = usize::from(!j.left.is_unit());
+                own + j.left.count_joins() + j.right.count_joins()
+            }
+            RelAlgebra::NegJoin(j) => 1 + j.left.count_joins() + j.right.count_joins(),
+            RelAlgebra::Reorder(r) => r.relation.count_joins(),
+            RelAlgebra::Filter(r) => r.parent.count_joins(),
+            RelAlgebra::Unification(r) => r.parent.count_joins(),
+            RelAlgebra::HnswSearch(r) => r.parent.count_joins(),
+            RelAlgebra::FtsSearch(r) => r.parent.count_joins(),
+            RelAlgebra::LshSearch(r) => r.parent.count_joins(),
+        }
+    }
 }
 
 pub(crate) struct UnificationRA {
@@ -408,9 +489,6 @@ impl RelAlgebra {
             false
         }
     }
-    pub(crate) fn cartesian_join(self, right: RelAlgebra, span: SourceSpan) -> Self {
-        self.join(right, vec![], vec![], span)
-    }
     pub(crate) fn derived(
         bindings: Vec<Symbol>,
         storage_key: MagicSymbol,
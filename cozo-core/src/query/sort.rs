@@ -7,46 +7,336 @@
  */
 
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BinaryHeap};
 
-use itertools::Itertools;
-use miette::Result;
+use itertools::{process_results, Itertools};
+use miette::{bail, Diagnostic, Result};
+use thiserror::Error;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
-use crate::data::program::SortDir;
+use crate::data::expr::{eval_bytecode, Bytecode};
+use crate::data::program::{SortCollate, SortDir, SortNulls, Sorter};
 use crate::data::symb::Symbol;
 use crate::data::tuple::Tuple;
+use crate::data::value::DataValue;
+use crate::parse::SourceSpan;
 use crate::runtime::temp_store::EpochStore;
 use crate::runtime::transact::SessionTx;
 
 impl<'a> SessionTx<'a> {
+    /// Sorts the query result by `sorters`. The sort is stable over the result store,
+    /// which is itself ordered by the whole tuple, so rows that tie on every sort key
+    /// come out in tuple order and the output is the same on every run.
+    ///
+    /// Computed and case-folded sort keys are derived once per row and appended to it
+    /// for the sort, then cut off again, so they never appear in the output.
     pub(crate) fn sort_and_collect(
         &mut self,
         original: EpochStore,
-        sorters: &[(Symbol, SortDir)],
+        sorters: &[Sorter],
         head: &[Symbol],
+        limit: Option<usize>,
+        offset: usize,
     ) -> Result<Vec<Tuple>> {
-        let head_indices: BTreeMap<_, _> = head.iter().enumerate().map(|(i, k)| (k, i)).collect();
-        let idx_sorters = sorters
-            .iter()
-            .map(|(k, dir)| (head_indices[k], *dir))
-            .collect_vec();
+        let (idx_sorters, derived) = resolve_sorters(sorters, head)?;
 
-        let mut all_data: Vec<_> = original.all_iter().map(|v| v.into_tuple()).collect_vec();
-        all_data.sort_by(|a, b| {
-            for (idx, dir) in &idx_sorters {
-                match a[*idx].cmp(&b[*idx]) {
-                    Ordering::Equal => {}
-                    o => {
-                        return match dir {
-                            SortDir::Asc => o,
-                            SortDir::Dsc => o.reverse(),
+        let data = original.all_iter().map(|v| v.into_tuple());
+        let k = limit.map(|limit| offset.saturating_add(limit));
+        let mut all_data = if derived.is_empty() {
+            sort_with_limit(data, &idx_sorters, k)
+        } else {
+            let mut stack = vec![];
+            let decorated = data.map(|mut tuple| -> Result<Tuple> {
+                for (source, collate) in &derived {
+                    let key = match source {
+                        KeySource::Column(idx) => tuple[*idx].clone(),
+                        KeySource::Expr(bytecode) => {
+                            eval_bytecode(bytecode, &tuple[..head.len()], &mut stack)?
                         }
-                    }
+                    };
+                    tuple.push(collation_key(*collate, key));
                 }
+                Ok(tuple)
+            });
+            // decorate lazily, so that a limited sort still keeps only `k` rows around
+            let mut sorted =
+                process_results(decorated, |rows| sort_with_limit(rows, &idx_sorters, k))?;
+            for tuple in sorted.iter_mut() {
+                tuple.truncate(head.len());
             }
-            Ordering::Equal
-        });
+            sorted
+        };
+
+        if offset >= all_data.len() {
+            return Ok(vec![]);
+        }
+        if let Some(limit) = limit {
+            all_data.truncate(offset.saturating_add(limit));
+        }
+        all_data.drain(..offset);
 
         Ok(all_data)
     }
 }
+
+type IdxSorter = (usize, SortDir, SortNulls);
+
+/// The value a string is compared by under `collate`; other values are compared as they are.
+/// `Unicode` compares strings with accents and case folded away first, and falls back to the
+/// original string for strings that are equal under folding.
+fn collation_key(collate: SortCollate, key: DataValue) -> DataValue {
+    match (collate, key) {
+        (SortCollate::NoCase, DataValue::Str(s)) => DataValue::from(s.to_lowercase()),
+        (SortCollate::Unicode, DataValue::Str(s)) => {
+            let folded: String = s
+                .nfkd()
+                .filter(|c| !is_combining_mark(*c))
+                .flat_map(char::to_lowercase)
+                .collect();
+            DataValue::List(vec![DataValue::from(folded), DataValue::Str(s)])
+        }
+        (_, key) => key,
+    }
+}
+
+/// Where a derived sort key comes from, before collation is applied.
+#[derive(Debug)]
+enum KeySource {
+    Column(usize),
+    Expr(Vec<Bytecode>),
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Sort key '{0}' is not among the output columns")]
+#[diagnostic(code(eval::unknown_sort_key))]
+struct UnknownSortKey(String, #[label] SourceSpan);
+
+/// Maps each sorter to a tuple position. A plain column compared byte-wise is sorted
+/// in place; computed or case-folded keys get positions past the end of the head,
+/// in the order of the returned derived keys.
+fn resolve_sorters(
+    sorters: &[Sorter],
+    head: &[Symbol],
+) -> Result<(Vec<IdxSorter>, Vec<(KeySource, SortCollate)>)> {
+    let head_indices: BTreeMap<_, _> = head
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, k)| (k, i))
+        .collect();
+    let mut derived = vec![];
+    let idx_sorters = sorters
+        .iter()
+        .map(|s| -> Result<IdxSorter> {
+            let source = match &s.expr {
+                None => match head_indices.get(&s.key) {
+                    Some(idx) => KeySource::Column(*idx),
+                    None => bail!(UnknownSortKey(s.key.to_string(), s.key.span)),
+                },
+                Some(expr) => {
+                    let mut expr = expr.clone();
+                    expr.fill_binding_indices(&head_indices)?;
+                    KeySource::Expr(expr.compile()?)
+                }
+            };
+            let idx = match (source, s.collate) {
+                (KeySource::Column(idx), SortCollate::Binary) => idx,
+                (source, collate) => {
+                    derived.push((source, collate));
+                    head.len() + derived.len() - 1
+                }
+            };
+            Ok((idx, s.dir, s.nulls))
+        })
+        .try_collect()?;
+    Ok((idx_sorters, derived))
+}
+
+fn sort_with_limit(
+    data: impl Iterator<Item = Tuple>,
+    sorters: &[IdxSorter],
+    k: Option<usize>,
+) -> Vec<Tuple> {
+    match k {
+        None => full_sort(data, sorters),
+        Some(k) => top_k_sort(data, sorters, k),
+    }
+}
+
+fn compare_tuples(a: &Tuple, b: &Tuple, sorters: &[IdxSorter]) -> Ordering {
+    for (idx, dir, nulls) in sorters {
+        let (x, y) = (&a[*idx], &b[*idx]);
+        let o = match (nulls, x, y) {
+            (SortNulls::First | SortNulls::Last, DataValue::Null, DataValue::Null) => {
+                Ordering::Equal
+            }
+            (SortNulls::First, DataValue::Null, _) | (SortNulls::Last, _, DataValue::Null) => {
+                Ordering::Less
+            }
+            (SortNulls::First, _, DataValue::Null) | (SortNulls::Last, DataValue::Null, _) => {
+                Ordering::Greater
+            }
+            _ => match dir {
+                SortDir::Asc => x.cmp(y),
+                SortDir::Dsc => x.cmp(y).reverse(),
+            },
+        };
+        if o != Ordering::Equal {
+            return o;
+        }
+    }
+    Ordering::Equal
+}
+
+fn full_sort(data: impl Iterator<Item = Tuple>, sorters: &[IdxSorter]) -> Vec<Tuple> {
+    let mut all_data = data.collect_vec();
+    all_data.sort_by(|a, b| compare_tuples(a, b, sorters));
+    all_data
+}
+
+/// Keeps only the first `k` tuples in sort order, using a max-heap bounded by `k`.
+/// Ties are broken by arrival order, so the result agrees with the stable full sort.
+fn top_k_sort(data: impl Iterator<Item = Tuple>, sorters: &[IdxSorter], k: usize) -> Vec<Tuple> {
+    struct HeapEntry<'s> {
+        tuple: Tuple,
+        seq: usize,
+        sorters: &'s [IdxSorter],
+    }
+
+    impl PartialEq for HeapEntry<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for HeapEntry<'_> {}
+
+    impl PartialOrd for HeapEntry<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for HeapEntry<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            compare_tuples(&self.tuple, &other.tuple, self.sorters)
+                .then_with(|| self.seq.cmp(&other.seq))
+        }
+    }
+
+    if k == 0 {
+        return vec![];
+    }
+
+    let mut heap = BinaryHeap::new();
+    for (seq, tuple) in data.enumerate() {
+        let entry = HeapEntry {
+            tuple,
+            seq,
+            sorters,
+        };
+        if heap.len() < k {
+            heap.push(entry);
+        } else if let Some(mut top) = heap.peek_mut() {
+            if entry < *top {
+                *top = entry;
+            }
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|entry| entry.tuple)
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::data::program::{SortCollate, SortDir, SortNulls, Sorter};
+    use crate::data::symb::Symbol;
+    use crate::data::value::DataValue;
+    use crate::parse::SourceSpan;
+    use crate::query::sort::{full_sort, resolve_sorters, top_k_sort};
+
+    #[test]
+    fn unknown_sort_key_is_an_error() {
+        let head = vec![
+            Symbol::new("a", SourceSpan(0, 0)),
+            Symbol::new("b", SourceSpan(0, 0)),
+        ];
+        let sorter = |name: &str, dir| Sorter {
+            key: Symbol::new(name, SourceSpan(0, 0)),
+            expr: None,
+            dir,
+            nulls: SortNulls::Natural,
+            collate: SortCollate::Binary,
+        };
+        let resolved = resolve_sorters(
+            &[sorter("b", SortDir::Dsc), sorter("a", SortDir::Asc)],
+            &head,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            resolved,
+            vec![
+                (1, SortDir::Dsc, SortNulls::Natural),
+                (0, SortDir::Asc, SortNulls::Natural)
+            ]
+        );
+        let err = resolve_sorters(&[sorter("c", SortDir::Asc)], &head).unwrap_err();
+        assert!(err.to_string().contains("'c'"));
+
+        let nocase = Sorter {
+            collate: SortCollate::NoCase,
+            ..sorter("a", SortDir::Asc)
+        };
+        let (resolved, derived) =
+            resolve_sorters(&[nocase, sorter("b", SortDir::Asc)], &head).unwrap();
+        assert_eq!(
+            resolved,
+            vec![
+                (2, SortDir::Asc, SortNulls::Natural),
+                (1, SortDir::Asc, SortNulls::Natural)
+            ]
+        );
+        assert_eq!(derived.len(), 1);
+    }
+
+    #[test]
+    fn top_k_agrees_with_full_sort() {
+        let data = (0..200)
+            .map(|i| {
+                vec![
+                    DataValue::from((i * 37 % 11) as i64),
+                    if i % 7 == 0 {
+                        DataValue::Null
+                    } else {
+                        DataValue::from((i * 13 % 5) as f64)
+                    },
+                    DataValue::from(i as i64),
+                ]
+            })
+            .collect_vec();
+        for sorters in [
+            vec![(0, SortDir::Asc, SortNulls::Natural)],
+            vec![(0, SortDir::Dsc, SortNulls::Natural)],
+            vec![
+                (1, SortDir::Asc, SortNulls::Natural),
+                (0, SortDir::Dsc, SortNulls::Natural),
+            ],
+            vec![
+                (1, SortDir::Dsc, SortNulls::Last),
+                (0, SortDir::Asc, SortNulls::Natural),
+            ],
+        ] {
+            let full = full_sort(data.iter().cloned(), &sorters);
+            for k in [0, 1, 5, 50, 199, 200, 500] {
+                let top = top_k_sort(data.iter().cloned(), &sorters, k);
+                assert_eq!(top, full.iter().take(k).cloned().collect_vec());
+            }
+        }
+    }
+}
@@ -1118,19 +1118,19 @@ struct TransactAssertionFailure {
 }
 
 enum DataExtractor {
-    DefaultExtractor(Expr, NullableColType),
-    IndexExtractor(usize, NullableColType),
+    DefaultExtractor(Expr, NullableColType, SmartString<LazyCompact>),
+    IndexExtractor(usize, NullableColType, SmartString<LazyCompact>),
 }
 
 impl DataExtractor {
     fn extract_data(&self, tuple: &Tuple, cur_vld: ValidityTs) -> Result<DataValue> {
         Ok(match self {
-            DataExtractor::DefaultExtractor(expr, typ) => typ
+            DataExtractor::DefaultExtractor(expr, typ, col) => typ
                 .coerce(expr.clone().eval_to_const()?, cur_vld)
-                .wrap_err_with(|| format!("when processing tuple {tuple:?}"))?,
-            DataExtractor::IndexExtractor(i, typ) => typ
+                .wrap_err_with(|| format!("when processing column '{col}' of tuple {tuple:?}"))?,
+            DataExtractor::IndexExtractor(i, typ, col) => typ
                 .coerce(tuple[*i].clone(), cur_vld)
-                .wrap_err_with(|| format!("when processing tuple {tuple:?}"))?,
+                .wrap_err_with(|| format!("when processing column '{col}' of tuple {tuple:?}"))?,
         })
     }
 }
@@ -1175,7 +1175,11 @@ fn make_extractor(
         if inp_col.name == stored.name {
             for (idx, tuple_head) in tuple_headers.iter().enumerate() {
                 if tuple_head == inp_binding {
-                    return Ok(DataExtractor::IndexExtractor(idx, stored.typing.clone()));
+                    return Ok(DataExtractor::IndexExtractor(
+                        idx,
+                        stored.typing.clone(),
+                        stored.name.clone(),
+                    ));
                 }
             }
         }
@@ -1184,6 +1188,7 @@ fn make_extractor(
         Ok(DataExtractor::DefaultExtractor(
             expr.clone(),
             stored.typing.clone(),
+            stored.name.clone(),
         ))
     } else {
         #[derive(Debug, Error, Diagnostic)]
@@ -10,10 +10,11 @@ use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
 use std::default::Default;
 use std::fmt::{Debug, Formatter};
+use std::io::Write;
 use std::iter;
 use std::path::Path;
 #[allow(unused_imports)]
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 #[allow(unused_imports)]
 use std::thread;
@@ -36,7 +37,7 @@ use crate::data::functions::current_validity;
 use crate::data::json::JsonValue;
 use crate::data::program::{InputProgram, QueryAssertion, RelationOp, ReturnMutation};
 use crate::data::relation::ColumnDef;
-use crate::data::tuple::{Tuple, TupleT};
+use crate::data::tuple::{FromTuple, Tuple, TupleT};
 use crate::data::value::{DataValue, ValidityTs, LARGEST_UTF_CHAR};
 use crate::fixed_rule::DEFAULT_FIXED_RULES;
 use crate::fts::TokenizerCache;
@@ -92,6 +93,58 @@ pub enum ScriptMutability {
     Immutable,
 }
 
+/// Limits checked against every query, before or during evaluation.
+/// Useful when the database runs scripts supplied by untrusted users.
+/// The default only caps the number of joins, generously.
+/// Build other limits from the default with the setters, as more limits may be added:
+///
+/// ```
+/// let limits = cozo::QueryLimits::default()
+///     .forbid_full_scans(true)
+///     .max_joins(Some(8));
+/// assert!(limits.forbid_full_scans);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryLimits {
+    /// Reject queries whose rules would scan a whole stored relation.
+    pub forbid_full_scans: bool,
+    /// Reject queries with a rule that joins more than this many atoms.
+    /// `None` means no limit.
+    pub max_joins: Option<usize>,
+    /// Abort a query once it has materialized about this many bytes into
+    /// temporary stores. `None` means no limit.
+    pub max_temp_bytes: Option<usize>,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            forbid_full_scans: false,
+            max_joins: Some(64),
+            max_temp_bytes: None,
+        }
+    }
+}
+
+impl QueryLimits {
+    /// Set whether queries that scan a whole stored relation are rejected.
+    pub fn forbid_full_scans(mut self, forbid: bool) -> Self {
+        self.forbid_full_scans = forbid;
+        self
+    }
+    /// Set the maximum number of atoms joined in a rule.
+    pub fn max_joins(mut self, max: Option<usize>) -> Self {
+        self.max_joins = max;
+        self
+    }
+    /// Set the maximum number of bytes a query may materialize into temporary stores.
+    pub fn max_temp_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_temp_bytes = max;
+        self
+    }
+}
+
 /// The database object of Cozo.
 #[derive(Clone)]
 pub struct Db<S> {
@@ -107,6 +160,9 @@ pub struct Db<S> {
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) event_callbacks: Arc<ShardedLock<EventCallbackRegistry>>,
     relation_locks: Arc<ShardedLock<BTreeMap<SmartString<LazyCompact>, Arc<ShardedLock<()>>>>>,
+    query_limits: Arc<ShardedLock<QueryLimits>>,
+    memory_accounting: Arc<AtomicBool>,
+    temp_bytes: Arc<AtomicUsize>,
 }
 
 impl<S> Debug for Db<S> {
@@ -176,6 +232,54 @@ impl NamedRows {
         collected
     }
 
+    /// Transpose the rows into one vector per column, in the order of the headers.
+    /// Only the current named rows are converted; `next` is dropped.
+    pub fn into_columns(self) -> Vec<Vec<DataValue>> {
+        let n = self.rows.len();
+        let mut columns: Vec<Vec<DataValue>> = (0..self.headers.len())
+            .map(|_| Vec::with_capacity(n))
+            .collect();
+        for row in self.rows {
+            for (col, val) in columns.iter_mut().zip(row) {
+                col.push(val);
+            }
+        }
+        columns
+    }
+
+    /// Convert each row into `T`, typically a tuple of Rust types in the order of the headers.
+    /// A row with the wrong number of columns or a column of the wrong type gives an error
+    /// for that row. Only the current named rows are converted; `next` is ignored.
+    pub fn rows_as<T: FromTuple>(&self) -> impl Iterator<Item = Result<T>> + '_ {
+        self.rows.iter().map(|row| T::from_tuple(row))
+    }
+
+    /// Write the rows as newline-delimited JSON, one object per row keyed by the headers.
+    /// Rows are converted one at a time as they are written, without building a JSON
+    /// document for the whole result. Only the current named rows are written; `next` is ignored.
+    /// Repeated headers would make columns collapse into one key, so they are rejected with an
+    /// `InvalidInput` error before anything is written.
+    pub fn write_json_lines(&self, mut out: impl Write) -> std::io::Result<()> {
+        let mut seen = BTreeSet::new();
+        if let Some(dup) = self.headers.iter().find(|h| !seen.insert(*h)) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("header '{dup}' occurs more than once"),
+            ));
+        }
+        for row in &self.rows {
+            let obj: serde_json::Map<String, JsonValue> = self
+                .headers
+                .iter()
+                .cloned()
+                .zip(row.iter().map(|v| JsonValue::from(v.clone())))
+                .collect();
+            serde_json::to_writer(&mut out, &obj)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
     /// Convert to a JSON object
     pub fn into_json(self) -> JsonValue {
         let nxt = match self.next {
@@ -266,6 +370,9 @@ impl<'s, S: Storage<'s>> Db<S> {
             #[cfg(not(target_arch = "wasm32"))]
             event_callbacks: Default::default(),
             relation_locks: Default::default(),
+            query_limits: Default::default(),
+            memory_accounting: Default::default(),
+            temp_bytes: Default::default(),
         };
         Ok(ret)
     }
@@ -282,7 +389,7 @@ impl<'s, S: Storage<'s>> Db<S> {
     /// the channels will fail.
     ///
     /// Write transactions _may_ block other reads, but we guarantee that this does not happen
-    /// for the RocksDB backend.
+    /// for the RocksDB backend. In a read transaction, a query that writes is rejected.
     pub fn run_multi_transaction(
         &'s self,
         is_write: bool,
@@ -353,6 +460,14 @@ impl<'s, S: Storage<'s>> Db<S> {
                             }
                         }
                     };
+                    if !is_write && p.needs_write_lock().is_some() {
+                        let err = miette!("write lock required for read-only query");
+                        if results.send(Err(err)).is_err() {
+                            break;
+                        } else {
+                            continue;
+                        }
+                    }
                     if let Some(write_lock_name) = p.needs_write_lock() {
                         match write_locks.entry(write_lock_name) {
                             Entry::Vacant(e) => {
@@ -748,6 +863,31 @@ impl<'s, S: Storage<'s>> Db<S> {
         Ok(self.fixed_rules.write().unwrap().remove(name).is_some())
     }
 
+    /// Set the limits checked against every query run through this database object
+    /// and its clones.
+    pub fn set_query_limits(&self, limits: QueryLimits) {
+        *self.query_limits.write().unwrap() = limits;
+    }
+
+    /// The limits currently checked against queries.
+    pub fn query_limits(&self) -> QueryLimits {
+        self.query_limits.read().unwrap().clone()
+    }
+
+    /// Turn memory accounting on or off for queries run through this database object and
+    /// its clones. While it is on, every query sizes the temporary stores it materializes
+    /// and adds the figure to [`temp_bytes`](Self::temp_bytes). It is off by default,
+    /// since sizing a store walks all of its tuples.
+    pub fn set_memory_accounting(&self, enabled: bool) {
+        self.memory_accounting.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Approximate number of bytes materialized into temporary stores by all queries
+    /// run while memory accounting was on.
+    pub fn temp_bytes(&self) -> usize {
+        self.temp_bytes.load(Ordering::Relaxed)
+    }
+
     /// Register callback channel to receive changes when the requested relation are successfully committed.
     /// The returned ID can be used to unregister the callback channel.
     #[cfg(not(target_arch = "wasm32"))]
@@ -840,6 +980,7 @@ impl<'s, S: Storage<'s>> Db<S> {
             temp_store_tx: self.temp_db.transact(true)?,
             relation_store_id: self.relation_store_id.clone(),
             temp_store_id: Default::default(),
+            temp_bytes: Default::default(),
             tokenizers: self.tokenizers.clone(),
         };
         Ok(ret)
@@ -850,6 +991,7 @@ impl<'s, S: Storage<'s>> Db<S> {
             temp_store_tx: self.temp_db.transact(true)?,
             relation_store_id: self.relation_store_id.clone(),
             temp_store_id: Default::default(),
+            temp_bytes: Default::default(),
             tokenizers: self.tokenizers.clone(),
         };
         Ok(ret)
@@ -1486,6 +1628,9 @@ impl<'s, S: Storage<'s>> Db<S> {
         let (stratified_program, store_lifetimes) = normalized_program.into_stratified_program()?;
         let program = stratified_program.magic_sets_rewrite(tx)?;
         let compiled = tx.stratified_magic_compile(program)?;
+        if top_level {
+            self.check_query_limits(&compiled)?;
+        }
 
         // poison is used to terminate queries early
         let poison = Poison::default();
@@ -1523,13 +1668,21 @@ impl<'s, S: Storage<'s>> Db<S> {
         };
 
         // the real evaluation
+        let account_temp_bytes = self.memory_accounting.load(Ordering::Relaxed);
+        let temp_bytes_at_start = tx.temp_bytes();
         let (result_store, early_return) = tx.stratified_magic_evaluate(
             &compiled,
             store_lifetimes,
             total_num_to_take,
             num_to_skip,
+            self.query_limits.read().unwrap().max_temp_bytes,
+            account_temp_bytes,
             poison,
         )?;
+        if account_temp_bytes {
+            self.temp_bytes
+                .fetch_add(tx.temp_bytes() - temp_bytes_at_start, Ordering::Relaxed);
+        }
 
         // deal with assertions
         if let Some(assertion) = &out_opts.assertion {
@@ -1559,18 +1712,14 @@ impl<'s, S: Storage<'s>> Db<S> {
 
         if !out_opts.sorters.is_empty() {
             // sort outputs if required
-            let sorted_result =
-                tx.sort_and_collect(result_store, &out_opts.sorters, &entry_head_or_default)?;
-            let sorted_iter = if let Some(offset) = out_opts.offset {
-                Left(sorted_result.into_iter().skip(offset))
-            } else {
-                Right(sorted_result.into_iter())
-            };
-            let sorted_iter = if let Some(limit) = out_opts.limit {
-                Left(sorted_iter.take(limit))
-            } else {
-                Right(sorted_iter)
-            };
+            let sorted_result = tx.sort_and_collect(
+                result_store,
+                &out_opts.sorters,
+                &entry_head_or_default,
+                out_opts.limit,
+                out_opts.offset.unwrap_or(0),
+            )?;
+            let sorted_iter = sorted_result.into_iter();
             if let Some((meta, relation_op, returning)) = &out_opts.store_relation {
                 let to_clear = tx
                     .execute_relation(
@@ -1667,6 +1816,48 @@ impl<'s, S: Storage<'s>> Db<S> {
             }
         }
     }
+    fn check_query_limits(&self, compiled: &[CompiledProgram]) -> Result<()> {
+        let limits = self.query_limits.read().unwrap();
+        if limits.forbid_full_scans {
+            #[derive(Debug, Error, Diagnostic)]
+            #[error("Query requires a full scan of stored relation '{0}'")]
+            #[diagnostic(code(eval::full_scan_forbidden))]
+            #[diagnostic(help(
+                "Bind a prefix of the keys of the relation, or bound its first key with a filter"
+            ))]
+            struct FullScanForbidden(String, #[label] SourceSpan);
+
+            for rule_set in compiled.iter().flat_map(|stratum| stratum.values()) {
+                if let CompiledRuleSet::Rules(rules) = rule_set {
+                    for rule in rules {
+                        if let Some((name, span)) = rule.relation.find_full_scan() {
+                            bail!(FullScanForbidden(name.to_string(), span))
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(max_joins) = limits.max_joins {
+            #[derive(Debug, Error, Diagnostic)]
+            #[error("Rule performs {0} joins, more than the limit of {1}")]
+            #[diagnostic(code(eval::too_many_joins))]
+            #[diagnostic(help("Split the rule into smaller rules, or raise the limit"))]
+            struct TooManyJoins(usize, usize, #[label] SourceSpan);
+
+            for rule_set in compiled.iter().flat_map(|stratum| stratum.values()) {
+                if let CompiledRuleSet::Rules(rules) = rule_set {
+                    for rule in rules {
+                        let joins = rule.relation.count_joins();
+                        if joins > max_joins {
+                            bail!(TooManyJoins(joins, max_joins, rule.relation.span()))
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn list_running(&self) -> Result<NamedRows> {
         let rows = self
             .running_queries
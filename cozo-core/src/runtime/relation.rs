@@ -477,6 +477,9 @@ impl RelationHandle {
         lower_t.extend_from_slice(lower);
         let mut upper_t = prefix.to_vec();
         upper_t.extend_from_slice(upper);
+        // bounds may also cover value columns, which are not part of the stored key
+        lower_t.truncate(self.metadata.keys.len());
+        upper_t.truncate(self.metadata.keys.len());
         upper_t.push(DataValue::Bot);
         let lower_encoded = lower_t.encode_as_key(self.id);
         let upper_encoded = upper_t.encode_as_key(self.id);
@@ -499,6 +502,9 @@ impl RelationHandle {
         lower_t.extend_from_slice(lower);
         let mut upper_t = prefix.clone();
         upper_t.extend_from_slice(upper);
+        // bounds may also cover value columns, which are not part of the stored key
+        lower_t.truncate(self.metadata.keys.len());
+        upper_t.truncate(self.metadata.keys.len());
         upper_t.push(DataValue::Bot);
         let lower_encoded = lower_t.encode_as_key(self.id);
         let upper_encoded = upper_t.encode_as_key(self.id);
@@ -243,6 +243,20 @@ impl TempStore {
             TempStore::MeetAggr(m) => m.inner.is_empty(),
         }
     }
+    /// Approximate number of bytes taken by the tuples in the store.
+    pub(crate) fn approx_bytes(&self) -> usize {
+        fn tuple_size(t: &Tuple) -> usize {
+            std::mem::size_of::<Tuple>() + t.iter().map(|v| v.approx_size()).sum::<usize>()
+        }
+        match self {
+            TempStore::Normal(n) => n.inner.keys().map(tuple_size).sum(),
+            TempStore::MeetAggr(m) => m
+                .inner
+                .iter()
+                .map(|(k, v)| tuple_size(k) + tuple_size(v))
+                .sum(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -23,7 +23,7 @@ use crate::fts::{TokenizerCache, TokenizerConfig};
 use crate::parse::SourceSpan;
 use crate::runtime::callback::CallbackOp;
 use crate::runtime::db::Poison;
-use crate::{DbInstance, FixedRule, RegularTempStore, ScriptMutability};
+use crate::{DbInstance, FixedRule, QueryLimits, RegularTempStore, ScriptMutability};
 
 #[test]
 fn test_limit_offset() {
@@ -1553,4 +1553,1628 @@ fn fts_drop() {
     db.run_default(r#"
         ::fts drop entity:fts_index
     "#).unwrap();
-}
\ No newline at end of file
+}
+#[test]
+fn self_referential_bindings() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[fr, to] <- [[1, 1], [1, 2], [2, 3], [3, 3]]
+        :create link {fr, to}
+    "#,
+    )
+    .unwrap();
+    let res = db.run_default("?[x] := *link[x, x]").unwrap().into_json();
+    assert_eq!(res["rows"], json!([[1], [3]]));
+    let res = db
+        .run_default("?[x] := *link{fr: x, to: x}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3]]));
+}
+
+#[test]
+fn negation_is_anti_join_on_bound_variables() {
+    let db = DbInstance::default();
+    db.run_default("?[e, deleted] <- [[1, true], [2, false]] :create person {e => deleted}")
+        .unwrap();
+    let res = db
+        .run_default("?[e] := e in [1, 2, 3], not *person{e, deleted: true}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2], [3]]));
+    let res = db.run_default("?[e] := e in [1, 2, 3], not *person{e: x}");
+    assert!(res.is_err());
+}
+
+#[test]
+fn disjunction_branches_must_bind_head() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[e, email, phone] <- [[1, 'a@b.c', null], [2, null, '123']]
+        :create person {e => email, phone}
+    "#,
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            ?[e, v] := (*person{e, email: v} or *person{e, phone: v}), !is_null(v)
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, "a@b.c"], [2, "123"]]));
+    let err = db
+        .run_default("?[e, v] := *person{e, email: v} or *person{e}")
+        .unwrap_err();
+    assert!(err.to_string().contains("'v'"));
+}
+
+#[test]
+fn test_sorted_limit_offset() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default("?[a] := a in [5,3,1,2,4] :order -a :limit 2 :offset 1")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[4], [3]]));
+    let res = db
+        .run_default("?[a] := a in [5,3,1,2,4] :order a :offset 3")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[4], [5]]));
+    let res = db
+        .run_default("?[a] := a in [5,3,1,2,4] :order a :limit 2 :offset 5")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([]));
+}
+
+#[test]
+fn sort_nulls_first_and_last() {
+    let db = DbInstance::default();
+    let data = "?[a] := a in [2, null, 1, 3, null]";
+    for (opt, expected) in [
+        (":order a", json!([[null], [1], [2], [3]])),
+        (":order -a", json!([[3], [2], [1], [null]])),
+        (":order a nulls last", json!([[1], [2], [3], [null]])),
+        (":order -a nulls first", json!([[null], [3], [2], [1]])),
+        (":order -a nulls last", json!([[3], [2], [1], [null]])),
+        (":order a nulls first :limit 2", json!([[null], [1]])),
+    ] {
+        let res = db
+            .run_default(&format!("{data} {opt}"))
+            .unwrap()
+            .into_json();
+        assert_eq!(res["rows"], expected, "{opt}");
+    }
+    assert!(db
+        .run_default(&format!("{data} :order a nullslast"))
+        .is_err());
+}
+
+#[test]
+fn unification_on_bound_variable_is_constraint() {
+    let db = DbInstance::default();
+    db.run_default("?[k, status] <- [[1, 'active'], [2, 'closed']] :create task {k => status}")
+        .unwrap();
+    let res = db
+        .run_default("?[k] := *task{k, status}, status = 'active'")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1]]));
+    let res = db
+        .run_default("?[k, status] := status = 'closed', *task{k, status}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2, "closed"]]));
+    let res = db
+        .run_default("?[k, status] := status in ['active', 'closed'], *task{k, status}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, "active"], [2, "closed"]]));
+}
+
+#[test]
+fn head_projects_away_unused_bindings() {
+    let db = DbInstance::default();
+    db.run_default("?[a, b, c] <- [[1, 2, 3], [1, 4, 5], [2, 2, 3]] :create abc {a, b, c}")
+        .unwrap();
+    let res = db.run_default("?[a] := *abc[a, b, c]").unwrap().into_json();
+    assert_eq!(res["rows"], json!([[1], [2]]));
+    let res = db
+        .run_default("?[c, a] := *abc[a, b, c], b > 1")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3, 1], [3, 2], [5, 1]]));
+    let res = db
+        .run_default("r[a] := *abc[a, b, c] ?[a] := r[a]")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [2]]));
+}
+
+#[test]
+fn rule_results_are_deduplicated() {
+    let db = DbInstance::default();
+    db.run_default("?[p, tag] <- [[1, 'a'], [1, 'b'], [2, 'a'], [2, 'c']] :create tagged {p, tag}")
+        .unwrap();
+    // each pair (x, y) is produced once per shared tag, but is returned only once
+    let res = db
+        .run_default("?[x, y] := *tagged[x, t], *tagged[y, t]")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, 1], [1, 2], [2, 1], [2, 2]]));
+}
+
+#[test]
+fn uuid_round_trip_and_range_scan() {
+    let db = DbInstance::default();
+    db.run_default(":create ext {id: Uuid => n: Int}").unwrap();
+    db.run_default(
+        r#"
+        ?[id, n] <- [[to_uuid('00000000-0000-0000-0000-000000000002'), 2],
+                     [to_uuid('00000000-0000-0000-0000-000000000001'), 1],
+                     ['ffffffff-0000-0000-0000-000000000000', 3]]
+        :put ext {id => n}
+    "#,
+    )
+    .unwrap();
+    let res = db.run_default("?[id, n] := *ext{id, n}").unwrap();
+    assert_eq!(
+        res.rows.iter().map(|row| row[1].clone()).collect_vec(),
+        vec![DataValue::from(1), DataValue::from(2), DataValue::from(3)]
+    );
+    assert!(matches!(res.rows[0][0], DataValue::Uuid(_)));
+    let res = db
+        .run_default(
+            r#"
+            ?[n] := *ext{id, n}, id < to_uuid('00000000-0000-0000-0000-000000000002')
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1]]));
+    let res = db
+        .run_default("?[id] := *ext{id, n: 3}")
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["ffffffff-0000-0000-0000-000000000000"]])
+    );
+}
+
+#[test]
+fn bytes_blob_round_trip() {
+    let db = DbInstance::default();
+    db.run_default(":create blob {k: Int => data: Bytes}")
+        .unwrap();
+    let blob = (0..1024).map(|i| (i * 7 % 256) as u8).collect_vec();
+    db.run_script(
+        "?[k, data] <- [[1, $data]] :put blob {k => data}",
+        BTreeMap::from([("data".into(), DataValue::Bytes(blob.clone()))]),
+        ScriptMutability::Mutable,
+    )
+    .unwrap();
+    db.run_default("?[k, data] <- [[2, 'AAEC']] :put blob {k => data}")
+        .unwrap();
+    let res = db
+        .run_default("?[k, data, l] := *blob{k, data}, l = length(data) :order -l")
+        .unwrap()
+        .rows;
+    assert_eq!(
+        res,
+        vec![
+            vec![
+                DataValue::from(1),
+                DataValue::Bytes(blob),
+                DataValue::from(1024)
+            ],
+            vec![
+                DataValue::from(2),
+                DataValue::Bytes(vec![0, 1, 2]),
+                DataValue::from(3)
+            ],
+        ]
+    );
+}
+
+#[test]
+fn optional_values_via_disjunction() {
+    let db = DbInstance::default();
+    db.run_default("?[e] <- [[1], [2]] :create person {e}")
+        .unwrap();
+    db.run_default("?[e, v] <- [[1, 'a@b.c']] :create email {e => v}")
+        .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            ?[e, v] := *person{e}, *email{e, v} or (*person{e}, not *email{e}, v = null)
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, "a@b.c"], [2, null]]));
+}
+
+#[test]
+fn forbid_full_scans() {
+    let db = DbInstance::default();
+    db.run_default("?[a, b] <- [[1, 2], [2, 3]] :create r {a => b}")
+        .unwrap();
+    db.run_default("?[a, b] <- [[1, 2], [2, 3]] :create s {a, b}")
+        .unwrap();
+    db.set_query_limits(QueryLimits::default().forbid_full_scans(true));
+    for allowed in [
+        "?[b] := *r{a: 1, b}",
+        "?[b] := a = 1, *r{a, b}",
+        "?[a, b] := *r{a, b}, a > 1",
+        "?[a, c] := a in [1, 2], *r{a, b}, *s{a: b, b: c}",
+        "?[a] := a in [1, 2], not *r{a}",
+        "?[a, b] <- [[3, 4]]",
+    ] {
+        assert!(db.run_default(allowed).is_ok(), "{allowed}");
+    }
+    for forbidden in [
+        "?[a, b] := *r{a, b}",
+        "?[a, b] := *r{a, b}, b > 1",
+        "?[a, b] := a in [1, 2], *s{a: b, b: a}",
+        "r[a] := *r{a} ?[a] := r[a]",
+    ] {
+        let err = db.run_default(forbidden).unwrap_err();
+        assert!(err.to_string().contains("full scan"), "{forbidden}");
+    }
+    db.set_query_limits(QueryLimits::default());
+    assert!(db.run_default("?[a, b] := *r{a, b}").is_ok());
+}
+
+#[test]
+fn set_difference_via_negation() {
+    let db = DbInstance::default();
+    db.run_default("?[k, v] <- [[1, 'a'], [2, 'b']] :create a {k => v}")
+        .unwrap();
+    db.run_default(":create b {k => v}").unwrap();
+    let query = "?[k, v] := *a{k, v}, not *b{k}";
+    let res = db.run_default(query).unwrap().into_json();
+    assert_eq!(res["rows"], json!([[1, "a"], [2, "b"]]));
+    db.run_default("?[k, v] <- [[2, 'x']] :put b {k => v}")
+        .unwrap();
+    let res = db.run_default(query).unwrap().into_json();
+    assert_eq!(res["rows"], json!([[1, "a"]]));
+    db.run_default("?[k, v] <- [[1, 'y']] :put b {k => v}")
+        .unwrap();
+    let res = db.run_default(query).unwrap().into_json();
+    assert_eq!(res["rows"], json!([]));
+}
+
+#[test]
+fn compound_filters_and_type_errors() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[name, age, country] <- [['a', 20, 'US'], ['b', 17, 'US'], ['c', 30, 'FR']]
+        :create person {name => age, country}
+    "#,
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            ?[name] := *person{name, age, country},
+                       (age > 18 && country == 'US') || !(country != 'FR')
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["a"], ["c"]]));
+    let err = db
+        .run_default("?[name] := *person{name, country}, country > 18")
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("comparison can only be done between the same datatypes"));
+}
+
+#[test]
+fn computed_bindings() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[item, price, qty] <- [['a', 2.5, 4], ['b', 3, 0]] :create order {item => price, qty}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[item, total] := *order{item, price, qty}, total = price * qty")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["a", 10.0], ["b", 0]]));
+    assert!(db
+        .run_default("?[item, per] := *order{item, price, qty}, per = price / qty")
+        .is_err());
+    assert!(db
+        .run_default("?[item, x] := *order{item, price}, x = price * item")
+        .is_err());
+}
+
+#[test]
+fn range_scan_across_zero() {
+    let db = DbInstance::default();
+    db.run_default("?[k] <- [[3], [-1], [0], [-5], [-100], [7], [1.5], [-0.5]] :create nums {k}")
+        .unwrap();
+    let res = db
+        .run_default("?[k] := *nums{k}, k >= -5, k < 3")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[-5], [-1], [-0.5], [0], [1.5]]));
+    let res = db.run_default("?[k] := *nums{k}").unwrap().into_json();
+    assert_eq!(
+        res["rows"],
+        json!([[-100], [-5], [-1], [-0.5], [0], [1.5], [3], [7]])
+    );
+}
+
+#[test]
+fn triangle_pattern_joins_consistently() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[fr, to] <- [[1, 2], [2, 3], [3, 1], [1, 3], [3, 4], [4, 1], [2, 4]] :create edge {fr, to}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[a, b, c] := *edge{fr: a, to: b}, *edge{fr: b, to: c}, *edge{fr: c, to: a}")
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            [1, 2, 3],
+            [1, 2, 4],
+            [1, 3, 4],
+            [2, 3, 1],
+            [2, 4, 1],
+            [3, 1, 2],
+            [3, 4, 1],
+            [4, 1, 2],
+            [4, 1, 3]
+        ])
+    );
+    let res = db
+        .run_default(
+            "?[a, b, c] := edge[a, b], edge[b, c], edge[c, a] edge[a, b] := *edge{fr: a, to: b}",
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"].as_array().unwrap().len(), 9);
+}
+
+#[test]
+fn semi_join_via_projected_rule() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, name] <- [[1, 'alice'], [2, 'bob'], [3, 'carol']] :create cust {id => name}",
+    )
+    .unwrap();
+    db.run_default(
+        "?[oid, cust] <- [[10, 1], [11, 1], [12, 1], [13, 3]] :create orders {oid => cust}",
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            has_order[c] := *orders{cust: c}
+            ?[count(name)] := *cust{id, name}, has_order[id]
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2]]));
+    let res = db
+        .run_default("?[count(name)] := *cust{id, name}, *orders{cust: id}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[4]]));
+}
+
+#[test]
+fn result_headers_follow_head_order() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default("?[b, a, count(c)] := x = [[1, 2, 3], [1, 2, 4]], l in x, a = get(l, 0), b = get(l, 1), c = get(l, 2), _ignored = a")
+        .unwrap();
+    assert_eq!(res.headers, vec!["b", "a", "count(c)"]);
+    assert_eq!(res.into_json()["rows"], json!([[2, 1, 2]]));
+}
+
+#[test]
+fn membership_in_constant_list() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, status] <- [[1, 'active'], [2, 'closed'], [3, 'pending'], [4, 'active']] :create acct {id => status}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id] := *acct{id, status}, status in ['active', 'pending']")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3], [4]]));
+    let res = db
+        .run_default("?[id] := *acct{id, status}, is_in(status, ['active', 'pending'])")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3], [4]]));
+    let res = db
+        .run_default("?[id] := *acct{id, status}, status in []")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([]));
+}
+
+#[test]
+fn lookup_of_missing_key_is_empty() {
+    let db = DbInstance::default();
+    db.run_default("?[email, name] <- [['a@x.com', 'alice']] :create person {email => name}")
+        .unwrap();
+    let res = db
+        .run_default("?[name] := *person{email: 'nobody@x.com', name}")
+        .unwrap();
+    assert!(res.rows.is_empty());
+    let res = db
+        .run_default("?[name] := *person{email: 'a@x.com', name}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["alice"]]));
+}
+
+#[test]
+fn join_against_inline_rows() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, name] <- [[1, 'alice'], [2, 'bob'], [3, 'carol']] :create user {id => name}",
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            scores[id, score] <- [[1, 0.9], [3, 0.5], [4, 0.1]]
+            ?[name, score] := scores[id, score], *user{id, name}
+            :order -score
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["alice", 0.9], ["carol", 0.5]]));
+    let params = BTreeMap::from([(
+        "scores".to_string(),
+        DataValue::List(vec![
+            DataValue::List(vec![DataValue::from(2), DataValue::from(0.7)]),
+            DataValue::List(vec![DataValue::from(5), DataValue::from(0.3)]),
+        ]),
+    )]);
+    let res = db
+        .run_script(
+            r#"
+            scores[id, score] <- $scores
+            ?[name, score] := scores[id, score], *user{id, name}
+            "#,
+            params,
+            ScriptMutability::Immutable,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["bob", 0.7]]));
+}
+
+#[test]
+fn case_insensitive_string_matching() {
+    let db = DbInstance::default();
+    db.run_default("?[id, name] <- [[1, 'Smith'], [2, 'SMITHSON'], [3, 'Émile Zola'], [4, 'Jones']] :create people {id => name}")
+        .unwrap();
+    let res = db
+        .run_default("?[id] := *people{id, name}, lowercase(name) == lowercase('smith')")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1]]));
+    let res = db
+        .run_default("?[id] := *people{id, name}, starts_with(lowercase(name), 'smith')")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [2]]));
+    let res = db
+        .run_default(
+            "?[id] := *people{id, name}, str_includes(lowercase(name), lowercase('ÉMILE'))",
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3]]));
+    let err = db
+        .run_default("?[id] := *people{id}, starts_with(lowercase(id), 'x')")
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("'lowercase' requires strings"));
+}
+
+#[test]
+fn composite_key_lookup() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[country, passport, name] <- [['fr', 1, 'amelie'], ['de', 1, 'bernd'], ['fr', 2, 'claude']]
+        :create citizen {country, passport => name}
+        "#,
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[name] := *citizen{country: 'fr', passport: 1, name}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["amelie"]]));
+    let res = db
+        .run_default("?[name] := *citizen{passport: 1, name}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["amelie"], ["bernd"]]));
+    assert!(db
+        .run_default("?[country, passport, name] <- [['fr', 1, 'other']] :insert citizen {country, passport => name}")
+        .is_err());
+}
+
+#[test]
+fn explain_reflects_compiled_plan() {
+    let db = DbInstance::default();
+    db.run_default(":create e {fr, to}").unwrap();
+    let res = db
+        .run_default("::explain { ?[a, c] := *e{fr: a, to: b}, *e{fr: b, to: c}, a > 1 }")
+        .unwrap();
+    assert_eq!(res.headers[4], "op");
+    assert_eq!(
+        res.into_json()["rows"],
+        json!([
+            [0, 0, "?", 3, "load_stored", ":e", null, ["gt(a, 1)"], ["a", "b"]],
+            [0, 0, "?", 2, "load_stored", ":e", null, [], ["**0", "c"]],
+            [0, 0, "?", 1, "stored_prefix_join", null, {"b": "**0"}, null, ["a", "c"]],
+            [0, 0, "?", 0, "out", null, null, null, ["a", "c"]]
+        ])
+    );
+}
+
+#[test]
+fn boolean_columns() {
+    let db = DbInstance::default();
+    db.run_default(":create member {id: Int => active: Bool}")
+        .unwrap();
+    db.run_default(
+        "?[id, active] <- [[1, true], [2, false], [3, true]] :put member {id => active}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id] := *member{id, active: true}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3]]));
+    let res = db
+        .run_default("?[id] := *member{id, active}, !active")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2]]));
+    let err = db
+        .run_default("?[id, active] <- [[4, 'true']] :put member {id => active}")
+        .unwrap_err();
+    let msg = format!("{err:?}");
+    assert!(msg.contains("active"), "{msg}");
+    assert!(msg.contains("Bool"), "{msg}");
+}
+
+#[test]
+fn group_counts_on_skewed_data() {
+    let db = DbInstance::default();
+    db.run_default("?[id, color] := id in int_range(1000), color = if(id % 100 == 0, if(id % 200 == 0, 'red', 'blue'), 'green') :create thing {id => color}")
+        .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            ?[color, count(id)] := *thing{id, color}
+            :order -count(id)
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["green", 990], ["blue", 5], ["red", 5]])
+    );
+}
+
+#[test]
+fn presence_and_absence_of_related_rows() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, name] <- [[1, 'alice'], [2, 'bob'], [3, 'carol']] :create person {id => name}",
+    )
+    .unwrap();
+    db.run_default("?[id, email] <- [[1, 'a@x.com'], [1, 'alice@y.com'], [3, 'c@x.com']] :create email {id, email}")
+        .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            has_email[id] := *email{id}
+            ?[name] := *person{id, name}, has_email[id]
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["alice"], ["carol"]]));
+    let res = db
+        .run_default("?[name] := *person{id, name}, not *email{id}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["bob"]]));
+    assert!(db.run_default("?[id] := not *email{id}").is_err());
+}
+
+#[test]
+fn batch_read_only_queries() {
+    let db = DbInstance::default();
+    db.run_default("?[k, v] <- [[1, 'a'], [2, 'b']] :create kv {k => v}")
+        .unwrap();
+    let q = |s: &str| (s.to_string(), BTreeMap::new());
+    let res = db.run_batch_read_only(vec![
+        q("?[v] := *kv{k: 1, v}"),
+        q("?[v] := *missing{v}"),
+        q("?[k, v] <- [[3, 'c']] :put kv {k => v}"),
+        q("?[count(k)] := *kv{k}"),
+    ]);
+    assert_eq!(res.len(), 4);
+    let mut res = res.into_iter();
+    assert_eq!(
+        res.next().unwrap().unwrap().into_json()["rows"],
+        json!([["a"]])
+    );
+    assert!(res.next().unwrap().is_err());
+    let err = res.next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+    assert_eq!(
+        res.next().unwrap().unwrap().into_json()["rows"],
+        json!([[2]])
+    );
+    assert_eq!(
+        db.run_default("?[count(k)] := *kv{k}").unwrap().into_json()["rows"],
+        json!([[2]])
+    );
+}
+
+#[test]
+fn sort_ties_are_deterministic() {
+    let db = DbInstance::default();
+    db.run_default("?[id, grp, name] := id in int_range(50), grp = id % 3, name = to_string(49 - id) :create tie {id => grp, name}")
+        .unwrap();
+    let q = "?[grp, name] := *tie{grp, name} :order grp :limit 20";
+    let first = db.run_default(q).unwrap().into_json();
+    let second = db.run_default(q).unwrap().into_json();
+    assert_eq!(first.to_string(), second.to_string());
+    let rows = first["rows"].as_array().unwrap();
+    assert_eq!(rows[0], json!([0, "1"]));
+    assert_eq!(rows[1], json!([0, "10"]));
+}
+
+#[test]
+fn runaway_query_times_out() {
+    let db = DbInstance::default();
+    let err = db
+        .run_default(
+            r#"
+            r[n] := n = 0
+            r[m] := r[n], m = n + 1
+            ?[count(n)] := r[n]
+            :timeout 0.05
+            "#,
+        )
+        .unwrap_err();
+    assert_eq!(err.code().unwrap().to_string(), "eval::killed");
+}
+
+#[test]
+fn list_values_and_containment() {
+    let db = DbInstance::default();
+    db.run_default(":create post {id: Int => tags: [String]}")
+        .unwrap();
+    db.run_default(
+        "?[id, tags] <- [[1, ['rust', 'db']], [2, ['go']], [3, ['db', 'sql', 'rust']], [4, []]] :put post {id => tags}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id] := *post{id, tags}, is_in('rust', tags)")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3]]));
+    let res = db
+        .run_default("?[tags] := *post{tags} :order tags")
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([[[]], [["db", "sql", "rust"]], [["go"]], [["rust", "db"]]])
+    );
+    let res = db
+        .run_default("?[nested] := nested = [['a', 'b'], ['c']], is_in(['c'], nested)")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[[["a", "b"], ["c"]]]]));
+    assert!(db
+        .run_default("?[id, tags] <- [[5, [1, 2]]] :put post {id => tags}")
+        .is_err());
+}
+
+#[test]
+fn key_range_is_a_bounded_seek() {
+    let db = DbInstance::default();
+    db.run_default("?[id, v] := id in int_range(3000), v = id * 2 :create block {id => v}")
+        .unwrap();
+    db.set_query_limits(QueryLimits::default().forbid_full_scans(true));
+    let res = db
+        .run_default("?[count(id), min(id), max(id)] := *block{id, v}, id >= 1000, id < 2000")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1000, 1000, 1999]]));
+    let res = db
+        .run_default("?[count(id), min(id)] := *block{id}, id >= 0")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3000, 0]]));
+    assert!(db
+        .run_default("?[count(id)] := *block{id, v}, v >= 1000, v < 2000")
+        .is_err());
+}
+
+#[test]
+fn intersection_by_conjunction() {
+    let db = DbInstance::default();
+    let q = |a: &str, b: &str| {
+        format!(
+            r#"
+            a[k, v] <- {a}
+            b[k] <- {b}
+            ?[k, v] := a[k, v], b[k]
+            "#
+        )
+    };
+    let run = |s: String| db.run_default(&s).unwrap().into_json()["rows"].clone();
+    assert_eq!(run(q("[[1, 'x'], [2, 'y']]", "[[3], [4]]")), json!([]));
+    assert_eq!(
+        run(q("[[1, 'x'], [2, 'y']]", "[[1], [2]]")),
+        json!([[1, "x"], [2, "y"]])
+    );
+    assert_eq!(
+        run(q(
+            "[[1, 'x'], [2, 'y'], [2, 'z'], [3, 'w']]",
+            "[[2], [3], [5], [2]]"
+        )),
+        json!([[2, "y"], [2, "z"], [3, "w"]])
+    );
+}
+
+#[test]
+fn typed_columns_reject_mismatched_values() {
+    let db = DbInstance::default();
+    db.run_default(":create typed {k: Int => f: Float, s: String}")
+        .unwrap();
+    db.run_default("?[k, f, s] <- [[1, 2, 'a']] :put typed {k => f, s}")
+        .unwrap();
+    let res = db
+        .run_default("?[k, f, s] := *typed{k, f, s}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, 2.0, "a"]]));
+    for (bad, col, typ) in [
+        ("[['2', 1.0, 'a']]", "k", "Int"),
+        ("[[2.5, 1.0, 'a']]", "k", "Int"),
+        ("[[2, 'x', 'a']]", "f", "Float"),
+        ("[[2, 1.0, 3]]", "s", "String"),
+    ] {
+        let err = db
+            .run_default(&format!("?[k, f, s] <- {bad} :put typed {{k => f, s}}"))
+            .unwrap_err();
+        let msg = format!("{err:?}");
+        assert!(msg.contains(&format!("column '{col}'")), "{msg}");
+        assert!(msg.contains(typ), "{msg}");
+    }
+}
+
+#[test]
+fn transitive_closure_terminates_on_cycles() {
+    let db = DbInstance::default();
+    db.run_default("?[fr, to] <- [[1, 2], [2, 3], [3, 1], [3, 4], [5, 6]] :create link {fr, to}")
+        .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            reach[a, b] := *link{fr: a, to: b}
+            reach[a, c] := reach[a, b], *link{fr: b, to: c}
+            ?[b] := reach[1, b]
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [2], [3], [4]]));
+    let res = db
+        .run_default(
+            r#"
+            reach[a, b, d] := *link{fr: a, to: b}, d = 1
+            reach[a, c, d] := reach[a, b, d0], *link{fr: b, to: c}, d0 < 2, d = d0 + 1
+            ?[b, min(d)] := reach[1, b, d]
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2, 1], [3, 2]]));
+}
+
+#[test]
+fn columnar_output() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default("?[a, b] <- [[1, 'x'], [2, 'y'], [3, 'z']]")
+        .unwrap();
+    assert_eq!(res.headers, vec!["a", "b"]);
+    assert_eq!(
+        res.into_columns(),
+        vec![
+            vec![DataValue::from(1), DataValue::from(2), DataValue::from(3)],
+            vec![
+                DataValue::from("x"),
+                DataValue::from("y"),
+                DataValue::from("z")
+            ],
+        ]
+    );
+    let res = db.run_default("?[a, b] <- [[1, 2]] :limit 0").unwrap();
+    assert_eq!(res.into_columns(), vec![vec![], vec![]]);
+}
+
+#[test]
+fn schema_introspection() {
+    let db = DbInstance::default();
+    db.run_default(
+        ":create person {id: Int => name: String, email: String?, tags: [String] default []}",
+    )
+    .unwrap();
+    db.run_default("::index create person:by_name {name}")
+        .unwrap();
+    let res = db.run_default("::columns person").unwrap();
+    assert_eq!(
+        res.headers,
+        ["column", "is_key", "index", "type", "has_default"]
+    );
+    assert_eq!(
+        res.into_json()["rows"],
+        json!([
+            ["id", true, 0, "Int", false],
+            ["name", false, 1, "String", false],
+            ["email", false, 2, "String?", false],
+            ["tags", false, 3, "[String]", true]
+        ])
+    );
+    let res = db.run_default("::relations").unwrap().into_json();
+    assert_eq!(res["rows"][0][0], json!("person"));
+    assert_eq!(res["rows"][1][0], json!("person:by_name"));
+    assert_eq!(res["rows"][1][2], json!("index"));
+    let res = db.run_default("::indices person").unwrap().into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["by_name", "normal", ["person:by_name"], {"indices": [1, 0]}]])
+    );
+}
+
+#[test]
+fn sort_on_three_columns_with_mixed_directions() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default(
+            r#"
+            ?[a, b, c] <- [[1, 2, 'z'], [2, 1, 'a'], [1, 2, 'a'], [1, 3, 'm'], [2, 1, 'b'], [1, 1, 'q']]
+            :order a, -b, c
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            [1, 3, "m"],
+            [1, 2, "a"],
+            [1, 2, "z"],
+            [1, 1, "q"],
+            [2, 1, "a"],
+            [2, 1, "b"]
+        ])
+    );
+    let res = db
+        .run_default(
+            r#"
+            ?[a, b, c] <- [[1, 2, 'z'], [2, 1, 'a'], [1, 2, 'a'], [1, 3, 'm'], [2, 1, 'b'], [1, 1, 'q']]
+            :order a, -b, c
+            :limit 3
+            :offset 1
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, 2, "a"], [1, 2, "z"], [1, 1, "q"]]));
+}
+
+#[test]
+fn full_text_match_modes() {
+    let db = DbInstance::default();
+    db.run_default(
+        r"?[id, body] <- [
+            [1, 'The quick brown fox jumps'],
+            [2, 'A brown quick fox'],
+            [3, 'The slow brown dog'],
+            [4, 'Foxes are QUICK']
+        ] :create doc {id => body}",
+    )
+    .unwrap();
+    db.run_default(
+        r"::fts create doc:body {
+            extractor: body,
+            tokenizer: Simple,
+            filters: [Lowercase]
+        }",
+    )
+    .unwrap();
+    let ids = |q: &str| {
+        db.run_script(
+            "?[id] := ~doc:body{id | query: $q, k: 10} :order id",
+            BTreeMap::from([("q".to_string(), DataValue::from(q))]),
+            ScriptMutability::Immutable,
+        )
+        .unwrap()
+        .into_json()["rows"]
+            .clone()
+    };
+    assert_eq!(ids("quick fox"), json!([[1], [2]]));
+    assert_eq!(ids("quick OR dog"), json!([[1], [2], [3], [4]]));
+    assert_eq!(ids("brown NOT fox"), json!([[3]]));
+    assert_eq!(ids("NEAR/1(quick fox)"), json!([[2]]));
+    assert_eq!(ids("NEAR/2(quick fox)"), json!([[1], [2]]));
+    let res = db
+        .run_default("?[id, s] := ~doc:body{id | query: 'quick OR brown', k: 1, bind_score: s}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn sort_on_mixed_type_column() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default(
+            r#"
+            ?[x] <- [['b'], [2], [null], ['a'], [1.5], [true], [[1]], [-3]]
+            :order x
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([[null], [true], [-3], [1.5], [2], ["a"], ["b"], [[1]]])
+    );
+    let res = db
+        .run_default(
+            r#"
+            ?[x] <- [['b'], [2], [null], ['a'], [1.5], [true], [[1]], [-3]]
+            :order -x nulls last
+            :limit 3
+            "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[[1]], ["b"], ["a"]]));
+}
+
+#[test]
+fn limit_without_sort_stops_early() {
+    let db = DbInstance::default();
+    db.run_default("?[x] := x in int_range(10000) :create big {x}")
+        .unwrap();
+    let res = db
+        .run_default("?[x] := *big{x}, x % 7 == 3 :limit 2")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3], [10]]));
+    // `assert` fails on every row after the second match, so the query only succeeds
+    // if evaluation stops as soon as the limit is reached.
+    let guarded = "?[x] := *big{x}, x % 7 == 3, assert(x <= 10, 'scanned past the limit')";
+    let err = db.run_default(guarded).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("scanned past the limit"),
+        "{err:?}"
+    );
+    let res = db
+        .run_default(&format!("{guarded} :limit 2"))
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3], [10]]));
+}
+
+#[test]
+fn named_parameters() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, name] <- [[1, 'alice'], [2, \"o'brien\"]] :create usr {id: Int => name: String}",
+    )
+    .unwrap();
+    let run = |params: Vec<(&str, DataValue)>| {
+        db.run_script(
+            "?[id] := *usr{id, name: $name}",
+            params
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            ScriptMutability::Immutable,
+        )
+    };
+    assert_eq!(
+        run(vec![("name", DataValue::from("o'brien"))])
+            .unwrap()
+            .into_json()["rows"],
+        json!([[2]])
+    );
+    assert_eq!(
+        run(vec![("name", DataValue::from("alice' OR 1"))])
+            .unwrap()
+            .rows
+            .len(),
+        0
+    );
+    assert!(run(vec![]).is_err());
+    assert!(db
+        .run_script(
+            "?[id, name] <- [[$id, 'carol']] :put usr {id => name}",
+            BTreeMap::from([("id".to_string(), DataValue::from("3"))]),
+            ScriptMutability::Mutable,
+        )
+        .is_err());
+}
+
+#[test]
+fn sort_collate_nocase() {
+    let db = DbInstance::default();
+    let data = "[['bob'], ['Alice'], ['alice'], ['Édith'], ['Carol'], ['ÉMILE'], [3], ['édith']]";
+    let res = db
+        .run_default(&format!("?[n] <- {data} :order n"))
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            [3],
+            ["Alice"],
+            ["Carol"],
+            ["alice"],
+            ["bob"],
+            ["ÉMILE"],
+            ["Édith"],
+            ["édith"]
+        ])
+    );
+    let res = db
+        .run_default(&format!("?[n] <- {data} :order n collate nocase"))
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            [3],
+            ["Alice"],
+            ["alice"],
+            ["bob"],
+            ["Carol"],
+            ["Édith"],
+            ["édith"],
+            ["ÉMILE"]
+        ])
+    );
+    let res = db
+        .run_default(&format!(
+            "?[n] <- {data} :order -n collate nocase nulls last :limit 3"
+        ))
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["ÉMILE"], ["Édith"], ["édith"]]));
+}
+
+#[test]
+fn sort_collate_unicode() {
+    let db = DbInstance::default();
+    let data = "[['zoe'], ['Émile'], ['eve'], ['édith'], ['Edith'], [null]]";
+    let res = db
+        .run_default(&format!("?[n] <- {data} :order n collate unicode"))
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([[null], ["Edith"], ["édith"], ["Émile"], ["eve"], ["zoe"]])
+    );
+    let res = db
+        .run_default(&format!(
+            "?[n] <- {data} :order -n collate unicode nulls last :limit 2"
+        ))
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["zoe"], ["eve"]]));
+}
+
+#[test]
+fn contradictory_constants_yield_nothing() {
+    let db = DbInstance::default();
+    db.run_default("?[id, age] <- [[42, 30]] :create p {id => age}")
+        .unwrap();
+    let res = db
+        .run_default("?[id] := *p{id: 42, age: 30}, *p{id: 42, age: 40}, id = 42")
+        .unwrap();
+    assert!(res.rows.is_empty());
+    let res = db
+        .run_default("?[id] := *p{id: 42, age: 30}, *p{id: 42}, id = 42")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[42]]));
+    let res = db
+        .run_default("?[id] := *p{id, age: 30}, *p{id, age: 40}")
+        .unwrap();
+    assert!(res.rows.is_empty());
+}
+
+#[test]
+fn json_lines_output() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default("?[i, s] := i in int_range(10000), s = to_string(i)")
+        .unwrap();
+    let mut buf = vec![];
+    res.write_json_lines(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    let lines = text.lines().collect_vec();
+    assert_eq!(lines.len(), 10000);
+    for (i, line) in lines.into_iter().enumerate() {
+        let parsed: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(parsed, json!({"i": i, "s": i.to_string()}));
+    }
+
+    let res = db.run_default("?[a, a] := a = 1").unwrap();
+    let mut buf = vec![];
+    let err = res.write_json_lines(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("'a'"), "{err}");
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn join_count_is_limited() {
+    let db = DbInstance::default();
+    db.run_default("?[a, b] <- [[1, 2], [2, 3], [3, 1]] :create edge {a => b}")
+        .unwrap();
+    let chain = |n: usize| {
+        let atoms = (0..n)
+            .map(|i| format!("*edge{{a: x{i}, b: x{}}}", i + 1))
+            .join(", ");
+        format!("?[x0, x{n}] := {atoms}")
+    };
+    assert_eq!(db.query_limits().max_joins, Some(64));
+    assert_eq!(db.run_default(&chain(65)).unwrap().rows.len(), 3);
+    let err = db.run_default(&chain(66)).unwrap_err();
+    assert_eq!(err.code().unwrap().to_string(), "eval::too_many_joins");
+    db.set_query_limits(QueryLimits::default().max_joins(Some(2)));
+    assert!(db.run_default(&chain(3)).is_ok());
+    assert!(db.run_default(&chain(4)).is_err());
+    assert!(db
+        .run_default("?[a] := *edge{a, b}, not *edge{a: b, b: a}, *edge{a: b}")
+        .is_ok());
+    assert!(db
+        .run_default("?[a] := *edge{a, b}, not *edge{a: b, b: a}, *edge{a: b}, *edge{b: a}")
+        .is_err());
+    db.set_query_limits(QueryLimits::default().max_joins(None));
+    assert!(db.run_default(&chain(100)).is_ok());
+}
+
+#[test]
+fn underscore_is_never_unified() {
+    let db = DbInstance::default();
+    db.run_default("?[a, b] <- [[1, 2], [3, 3]] :create pair {a, b}")
+        .unwrap();
+    let res = db.run_default("?[x] := *pair[_, _], x = 1").unwrap().rows;
+    assert_eq!(res.len(), 1);
+    let res = db
+        .run_default("?[a] := *pair{a: _, b: _}, a = 0")
+        .unwrap()
+        .rows;
+    assert_eq!(res.len(), 1);
+    let res = db
+        .run_default("r[a, b] := *pair[a, b] ?[n] := r[_, _], n = 1")
+        .unwrap()
+        .rows;
+    assert_eq!(res.len(), 1);
+    let res = db
+        .run_default("?[count(a)] := *pair[a, _], *pair[_, a]")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1]]));
+    let res = db
+        .run_default("?[a] := *pair[_x, _x], a = _x")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3]]));
+}
+
+#[test]
+fn distinct_values_from_an_index() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, colour] <- [[1, 'red'], [2, 'blue'], [3, 'red'], [4, 'green'], [5, 'red'], [6, 'blue']] :create item {id => colour}",
+    )
+    .unwrap();
+    db.run_default("::index create item:by_colour {colour}")
+        .unwrap();
+    let res = db
+        .run_default("?[colour] := *item:by_colour{colour}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["blue"], ["green"], ["red"]]));
+    let res = db
+        .run_default("?[colour, count(id)] := *item:by_colour{colour, id}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["blue", 2], ["green", 1], ["red", 3]]));
+    let res = db
+        .run_default("?[id] := *item:by_colour{colour: 'red', id}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1], [3], [5]]));
+}
+
+#[test]
+fn error_codes_identify_error_kinds() {
+    let db = DbInstance::default();
+    db.run_default(":create person {id: Int => name: String}")
+        .unwrap();
+    for (script, code) in [
+        ("?[x] := y = 1", "eval::unbound_symb_in_head"),
+        ("?[x] := not *person{id: x}", "eval::unsafe_negation"),
+        ("?[x] := r[x]", "eval::rule_not_found"),
+        (
+            "?[x] := *person{id: x, age: 1}",
+            "eval::named_field_not_found",
+        ),
+        (
+            "?[id, name] <- [['one', 'Ann']] :put person {id => name}",
+            "eval::coercion_failed",
+        ),
+        (
+            "?[x] := *person{id: x} :order y",
+            "parser::sort_key_not_found",
+        ),
+    ] {
+        let err = db.run_default(script).unwrap_err();
+        let mut found = vec![];
+        let mut cur: Option<&dyn miette::Diagnostic> = Some(err.as_ref());
+        while let Some(diag) = cur {
+            if let Some(c) = diag.code() {
+                found.push(c.to_string());
+            }
+            cur = diag.diagnostic_source();
+        }
+        assert!(found.iter().any(|c| c == code), "{script}: {found:?}");
+    }
+}
+
+#[test]
+fn attributes_by_namespace_prefix() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[e, attr, v] <- [
+            [1, 'person/name', 'Ann'],
+            [1, 'person/email', 'ann@x.com'],
+            [1, 'account/plan', 'pro'],
+            [2, 'person/name', 'Bob']
+        ]
+        :create eav {e, attr => v}
+    "#,
+    )
+    .unwrap();
+    let q = "?[attr, v] := *eav{e: 1, attr, v}, starts_with(attr, 'person/')";
+    let res = db.run_default(q).unwrap().into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["person/email", "ann@x.com"], ["person/name", "Ann"]])
+    );
+    db.run_default("?[e, attr, v] <- [[1, 'person/age', 30]] :put eav {e, attr => v}")
+        .unwrap();
+    let res = db.run_default(q).unwrap().into_json();
+    assert_eq!(
+        res["rows"],
+        json!([
+            ["person/age", 30],
+            ["person/email", "ann@x.com"],
+            ["person/name", "Ann"]
+        ])
+    );
+}
+
+#[test]
+fn typed_row_mapping() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, name, age] <- [[1, 'Ann', 31], [2, 'Bob', null]] :create person {id => name, age}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id, name, age] := *person{id, name, age}")
+        .unwrap();
+    let people: Vec<(i64, String, Option<i64>)> = res.rows_as().try_collect().unwrap();
+    assert_eq!(
+        people,
+        vec![
+            (1, "Ann".to_string(), Some(31)),
+            (2, "Bob".to_string(), None)
+        ]
+    );
+    let err = res.rows_as::<(i64, String)>().next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("2 columns, got 3"), "{err}");
+    let err = res
+        .rows_as::<(i64, i64, Option<i64>)>()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(err.to_string().contains("column 1"), "{err}");
+    assert!(
+        format!("{err:?}").contains("Expected an integer"),
+        "{err:?}"
+    );
+    let res = db.run_default("?[x] <- [[31.0]]").unwrap();
+    assert!(res.rows_as::<(i64,)>().next().unwrap().is_err());
+    let floats: Vec<(f64,)> = res.rows_as().try_collect().unwrap();
+    assert_eq!(floats, vec![(31.0,)]);
+}
+
+#[test]
+fn interval_overlap_join() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, start, end] <- [['a', 0, 10], ['b', 20, 30], ['c', 5, 25]] :create meeting {id => start, end}",
+    )
+    .unwrap();
+    db.run_default(
+        "?[id, start, end] <- [['x', 8, 12], ['y', 30, 40], ['z', 40, 50]] :create booking {id => start, end}",
+    )
+    .unwrap();
+    let res = db
+        .run_default(
+            r#"
+            ?[m, b] := *meeting{id: m, start: ms, end: me},
+                       *booking{id: b, start: bs, end: be},
+                       ms < be, bs < me
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["a", "x"], ["c", "x"]]));
+}
+
+#[test]
+fn temp_memory_accounting() {
+    let db = DbInstance::default();
+    let script = "?[x, y] := x in int_range(10000), y = to_string(x)";
+    assert_eq!(db.run_default(script).unwrap().rows.len(), 10000);
+    assert_eq!(db.temp_bytes(), 0);
+
+    db.set_memory_accounting(true);
+    assert_eq!(db.run_default(script).unwrap().rows.len(), 10000);
+    // Each row is a vector of two values plus the digits of `y`: 10 one-digit numbers,
+    // 90 two-digit, 900 three-digit and 9000 four-digit.
+    let digits = 10 + 90 * 2 + 900 * 3 + 9000 * 4;
+    let expected = 10000
+        * (std::mem::size_of::<Vec<DataValue>>() + 2 * std::mem::size_of::<DataValue>())
+        + digits;
+    let reported = db.temp_bytes();
+    // the estimate may pick up some bookkeeping, but must stay within 10% of the data
+    assert!(
+        expected <= reported && reported <= expected + expected / 10,
+        "{expected} {reported}"
+    );
+    db.set_memory_accounting(false);
+    db.run_default(script).unwrap();
+    assert_eq!(db.temp_bytes(), reported);
+
+    let db = DbInstance::default();
+    db.set_query_limits(QueryLimits::default().max_temp_bytes(Some(100_000)));
+    assert!(db.run_default("?[x] := x in int_range(100)").is_ok());
+    let err = db.run_default(script).unwrap_err();
+    assert_eq!(
+        err.code().unwrap().to_string(),
+        "eval::temp_memory_exceeded"
+    );
+    assert!(db.run_default("?[x] := x in int_range(100)").is_ok());
+    // the cap alone does not turn on reporting
+    assert_eq!(db.temp_bytes(), 0);
+}
+
+#[test]
+fn fresh_id_shared_within_one_write() {
+    let db = DbInstance::default();
+    db.run_default(":create eav {e: Uuid, attr: String => v}")
+        .unwrap();
+    for _ in 0..2 {
+        db.run_default(
+            r#"
+            new[e] := e = rand_uuid_v4()
+            ?[e, attr, v] := new[e], x in [['name', 'Ann'], ['email', 'ann@x.com']],
+                             attr = get(x, 0), v = get(x, 1)
+            :put eav {e, attr => v}
+        "#,
+        )
+        .unwrap();
+    }
+    let res = db
+        .run_default("?[e, count(attr)] := *eav{e, attr}")
+        .unwrap()
+        .rows;
+    assert_eq!(res.len(), 2);
+    assert!(res.iter().all(|row| row[1] == DataValue::from(2)));
+}
+
+#[test]
+fn find_values_stored_with_the_wrong_type() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[id, age] <- [[1, 30], [2, '41'], [3, 28.5], [4, null], [5, 52]] :create person {id => age}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id, age] := *person{id, age}, !is_int(age)")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2, "41"], [3, 28.5], [4, null]]));
+    let res = db
+        .run_default("?[id] := *person{id, age: '41'}")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[2]]));
+    let res = db
+        .run_default("?[id] := *person{id, age: 41}")
+        .unwrap()
+        .rows;
+    assert!(res.is_empty());
+}
+
+#[test]
+fn grouped_median_and_percentile() {
+    let db = DbInstance::default();
+    let res = db
+        .run_default(
+            r#"
+            data[g, id, v] <- [['a', 1, 1], ['a', 2, 9], ['a', 3, 5], ['b', 4, 2], ['b', 5, 2], ['b', 6, 4], ['b', 7, 8]]
+            ?[g, median(v), percentile(v, 0.75), mean(v)] := data[g, id, v]
+        "#,
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["a", 5.0, 7.0, 5.0], ["b", 3.0, 5.0, 4.0]])
+    );
+    assert!(db.run_default("?[median(v)] := v in [1, 'x']").is_err());
+}
+
+#[test]
+fn filter_on_nested_json_field() {
+    let db = DbInstance::default();
+    db.run_default(
+        r#"
+        ?[id, doc] <- [
+            [1, json({'address': {'city': 'NYC', 'zip': 10001}})],
+            [2, json({'address': {'city': 'LA'}})],
+            [3, json({'name': 'no address'})],
+            [4, json({'address': 'NYC'})],
+            [5, json({'address': {'city': 10001}})],
+            [6, 'NYC']
+        ]
+        :create doc {id => doc: Any}
+    "#,
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[id] := *doc{id, doc}, maybe_get(doc, ['address', 'city']) == 'NYC'")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1]]));
+    let res = db
+        .run_default(
+            "?[id, zip] := *doc{id, doc}, zip = maybe_get(doc, ['address', 'zip']), is_int(zip)",
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[1, 10001]]));
+    let res = db
+        .run_default("?[id] := *doc{id, doc}, is_null(maybe_get(doc, ['address', 'city']))")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([[3], [4], [6]]));
+}
+
+#[test]
+fn reproducible_sampling() {
+    let db = DbInstance::default();
+    db.run_default("?[id] := id in int_range(5000) :create event {id}")
+        .unwrap();
+    let sample = |seed: i64| {
+        db.run_default(&format!(
+            "?[id] := *event{{id}}, hash_bernoulli(id, 0.05, {seed})"
+        ))
+        .unwrap()
+        .rows
+    };
+    let first = sample(42);
+    assert_eq!(first, sample(42));
+    assert_ne!(first, sample(43));
+    assert!((150..350).contains(&first.len()), "{}", first.len());
+}
+
+#[test]
+fn sort_on_computed_expression() {
+    let db = DbInstance::default();
+    db.run_default(
+        "?[item, price, qty] <- [['a', 10, 1], ['b', 2, 20], ['c', 5, 3], ['d', 1, 7]] :create line {item => price, qty}",
+    )
+    .unwrap();
+    let res = db
+        .run_default("?[item, price, qty] := *line{item, price, qty} :order -(price * qty)")
+        .unwrap()
+        .into_json();
+    assert_eq!(res["headers"], json!(["item", "price", "qty"]));
+    assert_eq!(
+        res["rows"],
+        json!([["b", 2, 20], ["c", 5, 3], ["a", 10, 1], ["d", 1, 7]])
+    );
+    let res = db
+        .run_default(
+            "?[item] := *line{item, price, qty}, price > 1 :order (price * qty), item :limit 2",
+        )
+        .unwrap_err();
+    assert_eq!(
+        res.code().unwrap().to_string(),
+        "parser::sort_key_not_found"
+    );
+    let res = db
+        .run_default(
+            "?[item, price, qty] := *line{item, price, qty} :order (price * qty), item :limit 2",
+        )
+        .unwrap()
+        .into_json();
+    assert_eq!(res["rows"], json!([["d", 1, 7], ["a", 10, 1]]));
+    let res = db
+        .run_default("?[item, price] := *line{item, price} :order -(price % 3), item")
+        .unwrap()
+        .into_json();
+    assert_eq!(
+        res["rows"],
+        json!([["b", 2], ["c", 5], ["a", 10], ["d", 1]])
+    );
+}
+
+#[test]
+fn duplicate_columns_in_inline_rows_are_rejected() {
+    let db = DbInstance::default();
+    for script in [
+        "?[a, a] <- [[1, 2]]",
+        "r[a, b, a] <- [[1, 2, 3]] ?[x] := r[x, _, _]",
+    ] {
+        let err = db.run_default(script).unwrap_err();
+        assert_eq!(
+            err.code().unwrap().to_string(),
+            "parser::dup_binding_in_const_rule"
+        );
+        assert!(err.to_string().contains("'a'"), "{err}");
+    }
+    assert!(db.run_default("?[a, b] <- [[1, 2]]").is_ok());
+    assert!(db.run_default("?[a, a] := a = 1").is_ok());
+}
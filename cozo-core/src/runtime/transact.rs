@@ -6,7 +6,7 @@
  * You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::sync::atomic::{AtomicU32, AtomicU64};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use miette::{bail, Result};
@@ -26,6 +26,7 @@ pub struct SessionTx<'a> {
     pub(crate) temp_store_tx: TempTx,
     pub(crate) relation_store_id: Arc<AtomicU64>,
     pub(crate) temp_store_id: AtomicU32,
+    pub(crate) temp_bytes: AtomicUsize,
     pub(crate) tokenizers: Arc<TokenizerCache>,
 }
 
@@ -40,6 +41,12 @@ const STATUS_STR: &str = "status";
 const OK_STR: &str = "OK";
 
 impl<'a> SessionTx<'a> {
+    /// Approximate number of bytes materialized into temporary stores by queries
+    /// run in this transaction so far. Only counted while memory accounting is on or
+    /// `max_temp_bytes` is set.
+    pub(crate) fn temp_bytes(&self) -> usize {
+        self.temp_bytes.load(Ordering::Relaxed)
+    }
     pub(crate) fn get_returning_rows(&self, callback_collector: &mut CallbackCollector, rel: &str, returning: &ReturnMutation) -> Result<NamedRows> {
         let returned_rows = {
             match returning {